@@ -0,0 +1,211 @@
+/*!
+ * Dynamic shell completion for query paths
+ *
+ * The shell scripts generated here call back into `roc --complete <partial>` which walks
+ * the local doc tree to find the children of the partial path: module directories, the
+ * pages of items within a module and the method anchors on the page of a type.
+ */
+use crate::{
+    locate::{self, Locator, Tag, TaggedPath},
+    parse::DocParser,
+};
+use std::path::{Path, PathBuf};
+
+const BASH_SCRIPT: &str = r#"_roc() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    # query paths contain colons which bash treats as word breaks by default
+    if declare -F _get_comp_words_by_ref >/dev/null; then
+        _get_comp_words_by_ref -n : cur
+    fi
+    [[ "$cur" == -* ]] && return
+
+    COMPREPLY=($(roc --complete "$cur" 2>/dev/null))
+    if [[ ${#COMPREPLY[@]} -eq 1 && "${COMPREPLY[0]}" == *:: ]]; then
+        compopt -o nospace
+    fi
+    if declare -F __ltrim_colon_completions >/dev/null; then
+        __ltrim_colon_completions "$cur"
+    fi
+}
+
+complete -F _roc roc
+"#;
+
+const ZSH_SCRIPT: &str = r#"#compdef roc
+
+_roc() {
+    [[ "$PREFIX" == -* ]] && return 1
+
+    local -a candidates modules items
+    candidates=("${(@f)$(roc --complete "$PREFIX" 2>/dev/null)}")
+    modules=(${(M)candidates:#*::})
+    items=(${candidates:#*::})
+
+    compadd -Q -S '' -- "${modules[@]}"
+    compadd -Q -- "${items[@]}"
+}
+
+if [ "$funcstack[1]" = "_roc" ]; then
+    _roc "$@"
+else
+    compdef _roc roc
+fi
+"#;
+
+const FISH_SCRIPT: &str = r#"function __roc_complete
+    roc --complete (commandline -ct) 2>/dev/null
+end
+
+complete -c roc -f -n 'not string match -q -- "-*" (commandline -ct)' -a '(__roc_complete)'
+"#;
+
+/// The completion script for the given shell (bash, zsh or fish)
+pub fn shell_script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(BASH_SCRIPT),
+        "zsh" => Some(ZSH_SCRIPT),
+        "fish" => Some(FISH_SCRIPT),
+        _ => None,
+    }
+}
+
+/**
+ * Complete a partial query path against the local doc roots. Modules are completed with
+ * a trailing "::" so that completion can carry on into them, and a partial path of the
+ * form "Type.prefix" is completed to the methods documented for the type.
+ */
+pub fn complete(partial: &str) -> Vec<String> {
+    complete_in(&locate::doc_roots(), partial)
+}
+
+/// Complete a partial query path against the given doc roots
+pub(crate) fn complete_in(roots: &[PathBuf], partial: &str) -> Vec<String> {
+    if let Some((ty, prefix)) = partial.rsplit_once('.') {
+        return complete_method(ty, prefix);
+    }
+
+    complete_path(roots, partial)
+}
+
+fn complete_method(ty: &str, prefix: &str) -> Vec<String> {
+    let tagged_path = match Locator::try_new(ty.to_string()).and_then(|l| l.determine_tagged_path())
+    {
+        Some(p) => p,
+        None => return vec![],
+    };
+    if !matches!(
+        tagged_path.tag,
+        Tag::Struct | Tag::Enum | Tag::Trait | Tag::Primitive
+    ) {
+        return vec![];
+    }
+
+    let parser = match DocParser::try_new(tagged_path) {
+        Ok(parser) => parser,
+        Err(_) => return vec![],
+    };
+
+    parser
+        .method_names()
+        .into_iter()
+        .filter(|m| m.starts_with(prefix))
+        .map(|m| format!("{}.{}", ty, m))
+        .collect()
+}
+
+// Complete the last component of a module path from the doc trees under the given roots
+fn complete_path(roots: &[PathBuf], partial: &str) -> Vec<String> {
+    let (parent, prefix) = match partial.rsplit_once("::") {
+        Some((parent, prefix)) => (parent, prefix),
+        None => ("", partial),
+    };
+
+    let mut found = vec![];
+    if parent.is_empty() {
+        for root in roots {
+            let crates = locate::known_crate_dirs(root).unwrap_or_default();
+            found.extend(crates.into_iter().map(|c| format!("{}::", c)));
+        }
+    } else {
+        let components: Vec<&str> = parent.split("::").collect();
+        for dir in roots
+            .iter()
+            .map(|r| components.iter().fold(r.clone(), |p, c| p.join(c)))
+        {
+            found.extend(
+                module_children(&dir)
+                    .into_iter()
+                    .map(|c| format!("{}::{}", parent, c)),
+            );
+        }
+    }
+
+    let start = if parent.is_empty() {
+        0
+    } else {
+        parent.len() + 2
+    };
+    found.retain(|c| c[start..].starts_with(prefix));
+    found.sort();
+    found.dedup();
+    found
+}
+
+// The child modules (with a trailing "::") and the items documented in a module directory
+fn module_children(dir: &Path) -> Vec<String> {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return vec![],
+    };
+
+    entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            let name = path.file_name()?.to_str()?.to_string();
+            if path.is_dir() {
+                return path
+                    .join("index.html")
+                    .is_file()
+                    .then(|| format!("{}::", name));
+            }
+            if path.extension().is_some_and(|e| e == "html") {
+                let tagged = TaggedPath::from(path);
+                if !matches!(tagged.tag, Tag::Module | Tag::Unknown | Tag::Keyword) {
+                    return tagged.item_name();
+                }
+            }
+            None
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("", &["foo::"]; "crates")]
+    #[test_case("fo", &["foo::"]; "partial crate")]
+    #[test_case("foo::", &["foo::Kind", "foo::Thing", "foo::new"]; "items")]
+    #[test_case("foo::T", &["foo::Thing"]; "partial item")]
+    #[test_case("foo::bar::", &[]; "missing module")]
+    fn paths_are_completed(partial: &str, expected: &[&str]) {
+        let roots = vec![PathBuf::from("test_resources/diff/after")];
+
+        assert_eq!(complete_path(&roots, partial), expected);
+    }
+
+    #[test]
+    fn child_modules_are_completed() {
+        let roots = vec![PathBuf::from("test_resources/tree")];
+
+        assert_eq!(complete_path(&roots, "foo::"), vec!["foo::bar::"]);
+    }
+
+    #[test_case("bash")]
+    #[test_case("zsh")]
+    #[test_case("fish")]
+    fn scripts_call_the_completion_backend(shell: &str) {
+        assert!(shell_script(shell).unwrap().contains("roc --complete"));
+    }
+}
@@ -0,0 +1,441 @@
+/*!
+ * Resolve the symbol at a position in a source file to a query path
+ *
+ * This is used for editor integration: given a file:line:col we find the path or identifier
+ * under the cursor and resolve it through the `use` statements of the file, the module that
+ * it is written in and the prelude to get a fully qualified path that we can look up.
+ * There is no type inference so method calls on values can't be resolved.
+ */
+use crate::{
+    extract::{flatten_use_tree, item_attrs},
+    locate::manifest_package_name,
+};
+use proc_macro2::Span;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use syn::{
+    punctuated::Punctuated,
+    visit::{self, Visit},
+    Expr, ImplItemMethod, Item, ItemImpl, ItemMod, ItemTrait, ItemUse, Macro, Token,
+    TraitItemMethod, Type, UseTree,
+};
+
+// The maximum number of imports followed when resolving a path, to guard against cycles
+const MAX_IMPORT_DEPTH: usize = 8;
+
+// Names that are in scope everywhere via the std prelude, along with the path we look them
+// up under. Enum variants map to their enum as they don't have pages of their own.
+const PRELUDE: &[(&str, &str)] = &[
+    ("AsMut", "std::convert::AsMut"),
+    ("AsRef", "std::convert::AsRef"),
+    ("Box", "std::boxed::Box"),
+    ("Clone", "std::clone::Clone"),
+    ("Copy", "std::marker::Copy"),
+    ("Default", "std::default::Default"),
+    ("DoubleEndedIterator", "std::iter::DoubleEndedIterator"),
+    ("Drop", "std::ops::Drop"),
+    ("Eq", "std::cmp::Eq"),
+    ("Err", "std::result::Result"),
+    ("ExactSizeIterator", "std::iter::ExactSizeIterator"),
+    ("Extend", "std::iter::Extend"),
+    ("Fn", "std::ops::Fn"),
+    ("FnMut", "std::ops::FnMut"),
+    ("FnOnce", "std::ops::FnOnce"),
+    ("From", "std::convert::From"),
+    ("FromIterator", "std::iter::FromIterator"),
+    ("Into", "std::convert::Into"),
+    ("IntoIterator", "std::iter::IntoIterator"),
+    ("Iterator", "std::iter::Iterator"),
+    ("None", "std::option::Option"),
+    ("Ok", "std::result::Result"),
+    ("Option", "std::option::Option"),
+    ("Ord", "std::cmp::Ord"),
+    ("PartialEq", "std::cmp::PartialEq"),
+    ("PartialOrd", "std::cmp::PartialOrd"),
+    ("Result", "std::result::Result"),
+    ("Send", "std::marker::Send"),
+    ("Sized", "std::marker::Sized"),
+    ("Some", "std::option::Option"),
+    ("String", "std::string::String"),
+    ("Sync", "std::marker::Sync"),
+    ("ToOwned", "std::borrow::ToOwned"),
+    ("ToString", "std::string::ToString"),
+    ("TryFrom", "std::convert::TryFrom"),
+    ("TryInto", "std::convert::TryInto"),
+    ("Unpin", "std::marker::Unpin"),
+    ("Vec", "std::vec::Vec"),
+    ("drop", "std::mem::drop"),
+];
+
+// Macros exported from the root of std that can be used without an import
+const STD_MACROS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "cfg",
+    "column",
+    "concat",
+    "dbg",
+    "debug_assert",
+    "debug_assert_eq",
+    "debug_assert_ne",
+    "env",
+    "eprint",
+    "eprintln",
+    "file",
+    "format",
+    "format_args",
+    "include",
+    "include_bytes",
+    "include_str",
+    "line",
+    "matches",
+    "module_path",
+    "option_env",
+    "panic",
+    "print",
+    "println",
+    "stringify",
+    "todo",
+    "unimplemented",
+    "unreachable",
+    "vec",
+    "write",
+    "writeln",
+];
+
+/// Parse a "file:line:col" cursor position as given on the command line
+pub fn parse_position(position: &str) -> Option<(PathBuf, usize, usize)> {
+    let mut parts = position.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    let file = parts.next()?;
+
+    Some((PathBuf::from(file), line, column))
+}
+
+/**
+ * Find the path or identifier at the given line and column (both starting from 1) of a
+ * source file and resolve it to a fully qualified query path. Returns None if there is
+ * nothing that we can resolve at that position.
+ */
+pub fn query_at(file: &Path, line: usize, column: usize) -> Option<String> {
+    let file = fs::canonicalize(file).ok()?;
+    let syntax = syn::parse_file(&fs::read_to_string(&file).ok()?).ok()?;
+    let (krate, file_module) = module_of_file(&file)?;
+
+    let mut finder = Finder {
+        line,
+        column: column.checked_sub(1)?,
+        ..Default::default()
+    };
+    finder.visit_file(&syntax);
+    let found = finder.found.take()?;
+
+    let mut module = vec![krate];
+    module.extend(file_module);
+    let resolved = finder.resolve(&module, &found, 0);
+
+    Some(resolved.join("::"))
+}
+
+// The name of the crate that a file belongs to and the module path of the file within it
+fn module_of_file(file: &Path) -> Option<(String, Vec<String>)> {
+    let crate_dir = file.ancestors().find(|d| d.join("Cargo.toml").is_file())?;
+    let krate = manifest_package_name(&crate_dir.join("Cargo.toml"))?.replace('-', "_");
+
+    let relative = file
+        .strip_prefix(crate_dir.join("src"))
+        .ok()?
+        .with_extension("");
+    let mut module: Vec<String> = relative
+        .components()
+        .filter_map(|c| c.as_os_str().to_str().map(String::from))
+        .collect();
+    if module.len() == 1 && (module[0] == "lib" || module[0] == "main") {
+        module.clear();
+    } else if module.last().is_some_and(|m| m == "mod") {
+        module.pop();
+    }
+
+    Some((krate, module))
+}
+
+// A path found under the cursor along with the context needed to resolve it
+#[derive(Debug, Clone, Default)]
+struct Found {
+    segments: Vec<String>,
+    absolute: bool,
+    scope: Vec<String>,
+    self_ty: Option<Vec<String>>,
+}
+
+// An import from a `use` statement: the inline modules it is written in, the imported path
+// and the name that it is imported as
+#[derive(Debug, Clone)]
+struct Import {
+    scope: Vec<String>,
+    path: Vec<String>,
+    name: String,
+}
+
+#[derive(Debug, Default)]
+struct Finder {
+    line: usize,
+    column: usize,
+    scope: Vec<String>,
+    self_tys: Vec<Option<Vec<String>>>,
+    imports: Vec<Import>,
+    items: Vec<(Vec<String>, String)>,
+    found: Option<Found>,
+}
+
+impl Finder {
+    fn contains(&self, span: Span) -> bool {
+        let (start, end) = (span.start(), span.end());
+        start.line == self.line && start.column <= self.column && self.column < end.column
+    }
+
+    fn found(&mut self, segments: Vec<String>, absolute: bool) {
+        self.found = Some(Found {
+            segments,
+            absolute,
+            scope: self.scope.clone(),
+            self_ty: self.self_tys.last().cloned().flatten(),
+        });
+    }
+
+    // Find the identifier under the cursor within a use tree
+    fn find_in_use_tree(&mut self, tree: &UseTree, prefix: Vec<String>, absolute: bool) {
+        let ident = match tree {
+            UseTree::Path(p) => &p.ident,
+            UseTree::Name(n) => &n.ident,
+            UseTree::Rename(r) => &r.ident,
+            UseTree::Glob(_) => return,
+            UseTree::Group(g) => {
+                for t in g.items.iter() {
+                    self.find_in_use_tree(t, prefix.clone(), absolute);
+                }
+                return;
+            }
+        };
+
+        let mut path = prefix;
+        if ident != "self" {
+            path.push(ident.to_string());
+        }
+        if self.contains(ident.span()) {
+            self.found(path.clone(), absolute);
+        }
+        if let UseTree::Path(p) = tree {
+            self.find_in_use_tree(&p.tree, path, absolute);
+        }
+    }
+
+    // Resolve a found path to a fully qualified path given the module path of the file
+    fn resolve(&self, module: &[String], found: &Found, depth: usize) -> Vec<String> {
+        let segments = &found.segments;
+        let first = match segments.first() {
+            Some(s) if !found.absolute => s.as_str(),
+            _ => return segments.clone(),
+        };
+        let rest = &segments[1..];
+        let mut current: Vec<String> = module.to_vec();
+        current.extend(found.scope.iter().cloned());
+
+        let mut resolved = match first {
+            "crate" => vec![module[0].clone()],
+            "self" => current,
+            "super" => {
+                let supers = segments.iter().take_while(|s| *s == "super").count();
+                current.truncate(current.len().saturating_sub(supers).max(1));
+                current.extend(segments[supers..].iter().cloned());
+                return current;
+            }
+            "Self" => match &found.self_ty {
+                Some(ty) if depth < MAX_IMPORT_DEPTH => {
+                    let ty = Found {
+                        segments: ty.clone(),
+                        self_ty: None,
+                        ..found.clone()
+                    };
+                    self.resolve(module, &ty, depth + 1)
+                }
+                _ => return segments.clone(),
+            },
+            name => match self.import(&found.scope, name) {
+                Some(import) if depth < MAX_IMPORT_DEPTH => {
+                    let imported = Found {
+                        segments: import.path.clone(),
+                        absolute: false,
+                        scope: import.scope.clone(),
+                        self_ty: None,
+                    };
+                    self.resolve(module, &imported, depth + 1)
+                }
+                _ if self
+                    .items
+                    .contains(&(found.scope.clone(), name.to_string())) =>
+                {
+                    current.push(name.to_string());
+                    current
+                }
+                _ => match PRELUDE.iter().find(|(n, _)| *n == name) {
+                    Some((_, path)) => path.split("::").map(String::from).collect(),
+                    None if STD_MACROS.contains(&name) && rest.is_empty() => {
+                        vec![String::from("std"), name.to_string()]
+                    }
+                    None => vec![name.to_string()],
+                },
+            },
+        };
+
+        resolved.extend(rest.iter().cloned());
+        resolved
+    }
+
+    // The innermost import of a name that is visible from the given scope
+    fn import(&self, scope: &[String], name: &str) -> Option<&Import> {
+        self.imports
+            .iter()
+            .filter(|i| i.name == name && scope.starts_with(&i.scope))
+            .max_by_key(|i| i.scope.len())
+    }
+}
+
+impl<'ast> Visit<'ast> for Finder {
+    fn visit_item(&mut self, node: &'ast Item) {
+        if let Some((_, ident)) = item_attrs(node) {
+            self.items.push((self.scope.clone(), ident.to_string()));
+            if self.contains(ident.span()) {
+                self.found(vec![ident.to_string()], false);
+            }
+        }
+        visit::visit_item(self, node);
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast ItemMod) {
+        self.scope.push(node.ident.to_string());
+        visit::visit_item_mod(self, node);
+        self.scope.pop();
+    }
+
+    fn visit_item_use(&mut self, node: &'ast ItemUse) {
+        let absolute = node.leading_colon.is_some();
+        self.find_in_use_tree(&node.tree, vec![], absolute);
+
+        let mut flattened = vec![];
+        flatten_use_tree(&node.tree, vec![], &mut flattened);
+        for (path, name) in flattened {
+            if let Some(name) = name {
+                self.imports.push(Import {
+                    scope: self.scope.clone(),
+                    path,
+                    name,
+                });
+            }
+        }
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        let self_ty = match &*node.self_ty {
+            Type::Path(p) => Some(
+                p.path
+                    .segments
+                    .iter()
+                    .map(|s| s.ident.to_string())
+                    .collect(),
+            ),
+            _ => None,
+        };
+        self.self_tys.push(self_ty);
+        visit::visit_item_impl(self, node);
+        self.self_tys.pop();
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        self.self_tys.push(Some(vec![node.ident.to_string()]));
+        visit::visit_item_trait(self, node);
+        self.self_tys.pop();
+    }
+
+    fn visit_impl_item_method(&mut self, node: &'ast ImplItemMethod) {
+        if self.contains(node.sig.ident.span()) {
+            if let Some(Some(ty)) = self.self_tys.last().cloned() {
+                let mut segments = ty;
+                segments.push(node.sig.ident.to_string());
+                self.found(segments, false);
+            }
+        }
+        visit::visit_impl_item_method(self, node);
+    }
+
+    fn visit_trait_item_method(&mut self, node: &'ast TraitItemMethod) {
+        if self.contains(node.sig.ident.span()) {
+            if let Some(Some(ty)) = self.self_tys.last().cloned() {
+                let mut segments = ty;
+                segments.push(node.sig.ident.to_string());
+                self.found(segments, false);
+            }
+        }
+        visit::visit_trait_item_method(self, node);
+    }
+
+    // Macro arguments are opaque tokens, but most macros that are called with a cursor
+    // inside of them take comma separated expressions (println!, vec!, assert! etc)
+    fn visit_macro(&mut self, node: &'ast Macro) {
+        visit::visit_macro(self, node);
+        if let Ok(args) = node.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            args.iter().for_each(|e| self.visit_expr(e));
+        }
+    }
+
+    fn visit_path(&mut self, node: &'ast syn::Path) {
+        let segments: Vec<String> = node.segments.iter().map(|s| s.ident.to_string()).collect();
+        if let Some(i) = node
+            .segments
+            .iter()
+            .position(|s| self.contains(s.ident.span()))
+        {
+            self.found(segments[..=i].to_vec(), node.leading_colon.is_some());
+        }
+        visit::visit_path(self, node);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(6, 18, Some("std::collections::HashMap"); "imported type")]
+    #[test_case(6, 30, Some("hover_test::shapes::inner::Side"); "renamed import")]
+    #[test_case(11, 10, Some("hover_test::shapes::Square"); "self type")]
+    #[test_case(11, 33, Some("std::collections::HashMap::new"); "associated function")]
+    #[test_case(15, 8, Some("std::fmt::Display"); "grouped import")]
+    #[test_case(16, 35, Some("std::fmt::Formatter"); "imported module")]
+    #[test_case(17, 10, Some("std::write"); "std macro")]
+    #[test_case(17, 28, Some("std::option::Option"); "prelude")]
+    #[test_case(17, 32, Some("hover_test::shapes::Square"); "item in the same module")]
+    #[test_case(25, 25, Some("hover_test::shapes::Square::new"); "super path")]
+    #[test_case(10, 13, Some("hover_test::shapes::Square::new"); "method definition")]
+    #[test_case(3, 28, Some("hover_test::shapes::inner::Side"); "use statement")]
+    #[test_case(22, 16, Some("hover_test::shapes::inner::Side"); "item in inline module")]
+    #[test_case(4, 1, None; "nothing under the cursor")]
+    fn symbols_are_resolved(line: usize, column: usize, expected: Option<&str>) {
+        let file = Path::new("test_resources/cursor/src/shapes.rs");
+
+        assert_eq!(query_at(file, line, column).as_deref(), expected);
+    }
+
+    #[test_case("src/main.rs:12:5", Some(("src/main.rs", 12, 5)); "valid")]
+    #[test_case("C:/src/main.rs:12:5", Some(("C:/src/main.rs", 12, 5)); "colon in file name")]
+    #[test_case("src/main.rs:12", None; "missing column")]
+    fn positions_are_parsed(position: &str, expected: Option<(&str, usize, usize)>) {
+        let expected = expected.map(|(f, l, c)| (PathBuf::from(f), l, c));
+
+        assert_eq!(parse_position(position), expected);
+    }
+}
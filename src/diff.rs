@@ -0,0 +1,367 @@
+/*!
+ * Compare the public API documented in two rustdoc output trees
+ */
+use crate::{
+    locate::{self, Tag},
+    parse::DocParser,
+    pprint::{header, SECTION_HEADING_COLOR},
+};
+use serde_json::json;
+use std::{collections::BTreeMap, io, path};
+
+/// The ways in which a documented item can differ between two doc trees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    /// The item only exists in the newer tree
+    Added,
+    /// The item only exists in the older tree
+    Removed,
+    /// The item has been deprecated in the newer tree
+    Deprecated,
+    /// The signature of the item differs between the two trees
+    Changed {
+        /// The signature in the older tree
+        before: String,
+        /// The signature in the newer tree
+        after: String,
+    },
+}
+
+/// A single difference between two doc trees
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiChange {
+    /// The full path of the item (methods are given as Type.method)
+    pub path: String,
+    /// The kind of item that changed
+    pub tag: Tag,
+    /// What changed
+    pub change: Change,
+}
+
+// The signature and deprecation status of a single item in a doc tree
+#[derive(Debug, Clone)]
+struct ApiItem {
+    tag: Tag,
+    signature: String,
+    deprecated: bool,
+}
+
+/// Compare the std docs of two installed toolchains under the given module path
+pub fn toolchain_diff(toolchains: &[String], module: &str) -> io::Result<Vec<ApiChange>> {
+    if toolchains.len() != 2 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "exactly two toolchains are needed for a diff",
+        ));
+    }
+
+    let mut roots = vec![];
+    for name in toolchains {
+        match locate::toolchain_doc_root(name) {
+            Some(root) => roots.push(root),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("unable to locate docs for toolchain: {}", name),
+                ))
+            }
+        }
+    }
+
+    diff_trees(&roots[0], &roots[1], module)
+}
+
+/// The changes to all items under a module (given as a query path) between two doc roots
+pub fn diff_trees(
+    before: &path::Path,
+    after: &path::Path,
+    module: &str,
+) -> io::Result<Vec<ApiChange>> {
+    for root in &[before, after] {
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("not a documentation directory: {}", root.display()),
+            ));
+        }
+    }
+
+    let module_path: Vec<String> = module
+        .split("::")
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+
+    let old = collect_items(before, &module_path)?;
+    let new = collect_items(after, &module_path)?;
+    let mut changes = vec![];
+
+    for (path, item) in old.iter() {
+        match new.get(path) {
+            None => changes.push(ApiChange {
+                path: path.clone(),
+                tag: item.tag.clone(),
+                change: Change::Removed,
+            }),
+            Some(updated) => {
+                if updated.deprecated && !item.deprecated {
+                    changes.push(ApiChange {
+                        path: path.clone(),
+                        tag: updated.tag.clone(),
+                        change: Change::Deprecated,
+                    });
+                }
+                if normalise(&updated.signature) != normalise(&item.signature) {
+                    changes.push(ApiChange {
+                        path: path.clone(),
+                        tag: updated.tag.clone(),
+                        change: Change::Changed {
+                            before: item.signature.clone(),
+                            after: updated.signature.clone(),
+                        },
+                    });
+                }
+            }
+        }
+    }
+
+    for (path, item) in new.iter() {
+        if !old.contains_key(path) {
+            changes.push(ApiChange {
+                path: path.clone(),
+                tag: item.tag.clone(),
+                change: Change::Added,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Render a list of changes as sections of added, removed, deprecated and changed items
+pub fn render_changes(changes: &[ApiChange]) -> String {
+    let sections: Vec<String> = vec!["added", "removed", "deprecated", "changed"]
+        .into_iter()
+        .filter_map(|heading| {
+            let lines: Vec<String> = changes
+                .iter()
+                .filter(|c| change_heading(&c.change) == heading)
+                .map(|c| match &c.change {
+                    Change::Changed { before, after } => format!(
+                        "{:9} {}\n    - {}\n    + {}",
+                        c.tag.to_string(),
+                        c.path,
+                        before.replace('\n', "\n      "),
+                        after.replace('\n', "\n      ")
+                    ),
+                    _ => format!("{:9} {}", c.tag.to_string(), c.path),
+                })
+                .collect();
+
+            if lines.is_empty() {
+                None
+            } else {
+                Some(format!(
+                    "{}\n{}",
+                    header(heading, SECTION_HEADING_COLOR),
+                    lines.join("\n")
+                ))
+            }
+        })
+        .collect();
+
+    if sections.is_empty() {
+        String::from("No API changes found")
+    } else {
+        sections.join("\n\n")
+    }
+}
+
+/// Render a list of changes as a JSON report
+pub fn changes_as_json(changes: &[ApiChange]) -> String {
+    let entries: Vec<serde_json::Value> = changes
+        .iter()
+        .map(|c| {
+            let mut entry = json!({
+                "path": c.path,
+                "kind": c.tag.to_string(),
+                "change": change_heading(&c.change),
+            });
+            if let Change::Changed { before, after } = &c.change {
+                entry["before"] = json!(before);
+                entry["after"] = json!(after);
+            }
+            entry
+        })
+        .collect();
+
+    json!({ "changes": entries }).to_string()
+}
+
+fn change_heading(change: &Change) -> &'static str {
+    match change {
+        Change::Added => "added",
+        Change::Removed => "removed",
+        Change::Deprecated => "deprecated",
+        Change::Changed { .. } => "changed",
+    }
+}
+
+// Every item (and inherent method) documented under a module, keyed by its item path
+fn collect_items(
+    root: &path::Path,
+    module_path: &[String],
+) -> io::Result<BTreeMap<String, ApiItem>> {
+    let mut items = BTreeMap::new();
+
+    for tagged in locate::walk_tagged_paths(root, module_path)? {
+        let item_path = tagged.item_path();
+        let tag = tagged.tag.clone();
+        let api = DocParser::try_new(tagged)?.api_signature();
+
+        for (name, signature) in api.variants {
+            items.insert(
+                format!("{}::{}", item_path, name),
+                ApiItem {
+                    tag: Tag::Variant,
+                    signature,
+                    deprecated: false,
+                },
+            );
+        }
+
+        for (name, signature) in api.trait_impls {
+            items.insert(
+                format!("{} impl {}", item_path, name),
+                ApiItem {
+                    tag: Tag::TraitImpl,
+                    signature,
+                    deprecated: false,
+                },
+            );
+        }
+
+        for (name, signature, deprecated) in api.methods {
+            items.insert(
+                format!("{}.{}", item_path, name),
+                ApiItem {
+                    tag: Tag::Method,
+                    signature,
+                    deprecated,
+                },
+            );
+        }
+
+        items.insert(
+            item_path,
+            ApiItem {
+                tag,
+                signature: api.declaration,
+                deprecated: api.deprecated,
+            },
+        );
+    }
+
+    Ok(items)
+}
+
+// Whitespace differences in the rendered HTML are not API changes
+fn normalise(signature: &str) -> String {
+    signature
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changes_between_doc_trees() {
+        let changes = diff_trees(
+            path::Path::new("test_resources/diff/before"),
+            path::Path::new("test_resources/diff/after"),
+            "foo",
+        )
+        .unwrap();
+
+        let summary: Vec<(String, Tag, &str)> = changes
+            .iter()
+            .map(|c| (c.path.clone(), c.tag.clone(), change_heading(&c.change)))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("foo::Kind".into(), Tag::Enum, "changed"),
+                ("foo::Kind::Big".into(), Tag::Variant, "changed"),
+                ("foo::Kind::Small".into(), Tag::Variant, "removed"),
+                ("foo::Thing".into(), Tag::Struct, "deprecated"),
+                ("foo::Thing impl Clone".into(), Tag::TraitImpl, "changed"),
+                ("foo::Thing impl Debug".into(), Tag::TraitImpl, "removed"),
+                ("foo::Thing.get".into(), Tag::Method, "changed"),
+                ("foo::old".into(), Tag::Function, "removed"),
+                ("foo::Kind::Huge".into(), Tag::Variant, "added"),
+                ("foo::new".into(), Tag::Function, "added"),
+            ]
+        );
+    }
+
+    #[test]
+    fn changes_between_current_rustdoc_trees() {
+        let changes = diff_trees(
+            path::Path::new("test_resources/diff/current/before"),
+            path::Path::new("test_resources/diff/current/after"),
+            "foo",
+        )
+        .unwrap();
+
+        let summary: Vec<(String, Tag, &str)> = changes
+            .iter()
+            .map(|c| (c.path.clone(), c.tag.clone(), change_heading(&c.change)))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                ("foo::Thing".into(), Tag::Struct, "deprecated"),
+                ("foo::Thing impl Debug".into(), Tag::TraitImpl, "removed"),
+                ("foo::Thing.get".into(), Tag::Method, "changed"),
+                ("foo::Thing.len".into(), Tag::Method, "deprecated"),
+                ("foo::Thing impl From<T>".into(), Tag::TraitImpl, "added"),
+            ]
+        );
+        assert_eq!(
+            changes[2].change,
+            Change::Changed {
+                before: "pub fn get(&self) -> u8".into(),
+                after: "pub fn get(&self) -> u16".into(),
+            }
+        );
+    }
+
+    #[test]
+    fn changes_as_json_report() {
+        let changes = vec![
+            ApiChange {
+                path: "foo::new".into(),
+                tag: Tag::Function,
+                change: Change::Added,
+            },
+            ApiChange {
+                path: "foo::Thing.get".into(),
+                tag: Tag::Method,
+                change: Change::Changed {
+                    before: "pub fn get(&self) -> u8".into(),
+                    after: "pub fn get(&self) -> u16".into(),
+                },
+            },
+        ];
+
+        let report: serde_json::Value = serde_json::from_str(&changes_as_json(&changes)).unwrap();
+        assert_eq!(report["changes"][0]["kind"], "fn");
+        assert_eq!(report["changes"][0]["change"], "added");
+        assert_eq!(report["changes"][1]["after"], "pub fn get(&self) -> u16");
+    }
+}
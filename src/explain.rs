@@ -0,0 +1,147 @@
+/*!
+ * Look up explanations of compiler error codes using `rustc --explain`
+ */
+use crate::{
+    locate::{get_sys_root, rustc_command},
+    parse::matching_lines,
+    pprint::{self, header, markdown_code_blocks, render_markdown, SECTION_HEADING_COLOR},
+};
+use std::{io, process};
+
+// The number of `rustc --explain` processes that we run at once when listing codes
+const EXPLAIN_BATCH_SIZE: usize = 32;
+
+/// Is this query a compiler error code such as E0502?
+pub fn is_error_code(query: &str) -> bool {
+    query.len() == 5 && query.starts_with('E') && query[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+/// The raw Markdown explanation of an error code from rustc (if it is a known code)
+pub fn explanation(code: &str) -> Option<String> {
+    let out = rustc_command().arg("--explain").arg(code).output().ok()?;
+
+    if !out.status.success() {
+        return None;
+    }
+    String::from_utf8(out.stdout).ok()
+}
+
+/// Render the explanation of an error code, optionally showing only the code examples
+pub fn show_explanation(code: &str, grep: Option<String>, examples: bool) {
+    let md = match explanation(code) {
+        Some(md) => md,
+        None => {
+            println!("{} is not a known error code", code);
+            process::exit(1);
+        }
+    };
+
+    let rendered = if examples {
+        markdown_code_blocks(&md).join("\n\n")
+    } else {
+        render_markdown(&md)
+    };
+
+    let s = match grep {
+        Some(grep_str) => match matching_lines(rendered, &grep_str) {
+            Ok(lines) => lines,
+            Err(e) => panic!("{}", e),
+        },
+        None => rendered,
+    };
+
+    println!("{}", s);
+}
+
+/// List every error code known to rustc along with the first line of its explanation
+pub fn list_error_codes() -> io::Result<()> {
+    let mut rows: Vec<Vec<String>> = vec![];
+
+    for batch in known_error_codes()?.chunks(EXPLAIN_BATCH_SIZE) {
+        let children = batch
+            .iter()
+            .map(|code| {
+                rustc_command()
+                    .arg("--explain")
+                    .arg(code)
+                    .stdout(process::Stdio::piped())
+                    .stderr(process::Stdio::null())
+                    .spawn()
+                    .map(|child| (code, child))
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+
+        for (code, child) in children {
+            let out = child.wait_with_output()?;
+            if out.status.success() {
+                let md = String::from_utf8_lossy(&out.stdout);
+                rows.push(vec![code.clone(), first_line(&md)]);
+            }
+        }
+    }
+
+    let title = header("error codes", SECTION_HEADING_COLOR);
+    println!("{}\n{}", title, pprint::Table::from_rows(rows).as_string());
+    Ok(())
+}
+
+// Codes with an explanation page in the rust-docs error index, falling back to
+// trying every possible code if the docs are not installed.
+fn known_error_codes() -> io::Result<Vec<String>> {
+    let dir = get_sys_root().map(|r| r.join("share/doc/rust/html/error_codes"));
+
+    let mut codes: Vec<String> = match dir {
+        Some(d) if d.is_dir() => d
+            .read_dir()?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .filter_map(|f| f.strip_suffix(".html").map(String::from))
+            .filter(|c| is_error_code(c))
+            .collect(),
+        _ => (1..1000).map(|n| format!("E{:04}", n)).collect(),
+    };
+
+    codes.sort();
+    Ok(codes)
+}
+
+// The first line of prose in an explanation, noting codes that are no longer emitted
+fn first_line(md: &str) -> String {
+    let retired = md.contains("no longer emitted");
+    let line = md
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .unwrap_or_default()
+        .replace('`', "");
+
+    if retired {
+        format!("(no longer emitted) {}", line)
+    } else {
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("E0502", true)]
+    #[test_case("E12345", false)]
+    #[test_case("e0502", false)]
+    #[test_case("std", false)]
+    fn error_codes_are_detected(query: &str, expected: bool) {
+        assert_eq!(is_error_code(query), expected);
+    }
+
+    #[test]
+    fn first_line_skips_headings() {
+        let md = "#### Note: this error code is no longer emitted by the compiler.\n\nThis error suggests `foo`.\n";
+
+        assert_eq!(
+            first_line(md),
+            "(no longer emitted) This error suggests foo."
+        );
+    }
+}
@@ -2,132 +2,2065 @@
 //!
 //! The primary entrypoint for this module is the extract_items function which returns the parsed
 //! items found within the target module.
-// TODO: handle impl blocks being written in different files.
+//!
+//! The extract_crate function follows `mod` declarations from a crate root to extract every
+//! module in the crate, attaching impl blocks to their types wherever they are written.
+use crate::{pprint::max_width, signature};
+use proc_macro2::{Delimiter, Ident, Spacing, Span, TokenStream, TokenTree};
 use quote::ToTokens;
-use std::fmt;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs, io,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
 use syn::{
-    visit::{self, Visit},
-    Attribute, File, ItemFn, Lit, Meta, Result, Signature,
+    parse::{ParseStream, Parser},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit::Visit,
+    Attribute, Expr, ExprLit, Fields, Generics, ImplItem, Item, ItemConst, ItemEnum, ItemFn,
+    ItemImpl, ItemMacro, ItemMod, ItemStatic, ItemStruct, ItemTrait, ItemType, ItemUnion, ItemUse,
+    Lit, LitStr, Meta, NestedMeta, Result, Signature, Token, TraitItem, Type, UseTree, Visibility,
 };
 
+// Indentation used for the items within a declaration
+const INDENT: &str = "    ";
+
+/// Where an item is defined: lines and columns start from 1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    /// The lines spanned by the full definition, not including its doc comments
+    pub lines: RangeInclusive<usize>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file.display(), self.line, self.column)
+    }
+}
+
+/// The visibility of an item as it is written in source
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Vis {
+    /// Visible outside the crate
+    Public,
+    /// `pub(crate)`, `pub(super)` or `pub(in path)`
+    Restricted(String),
+    /// No visibility given: private to the module, or that of the enclosing trait
+    #[default]
+    Inherited,
+}
+
+impl Vis {
+    pub fn is_pub(&self) -> bool {
+        *self == Vis::Public
+    }
+}
+
+// Visibility is written with a trailing space so it can be prepended to a declaration
+impl fmt::Display for Vis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Vis::Public => write!(f, "pub "),
+            Vis::Restricted(r) => write!(f, "{} ", r),
+            Vis::Inherited => Ok(()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Module {
     name: String,
+    vis: Vis,
     docs: Option<String>,
 }
 
 impl fmt::Display for Module {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(s) = &self.docs {
-            write!(f, "[{}]\n{}", self.name, s)
-        } else {
-            write!(f, "[{}]", self.name)
-        }
+        let decl = format!("{}mod {};", self.vis, self.name);
+        write!(f, "{}", with_docs(&self.docs, decl))
     }
 }
 
+/// A function signature. Associated consts and types in traits and impl blocks are
+/// also stored as Functions with their declaration as the signature.
 #[derive(Debug, Clone)]
 pub struct Function {
-    // name: String,
-    // vis: String,
+    name: String,
+    vis: Vis,
     sig: String,
     docs: Option<String>,
+    location: Location,
 }
 
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        if let Some(s) = &self.docs {
-            write!(f, "{}\n{}", s, self.sig)
+        write!(
+            f,
+            "{}",
+            with_docs(&self.docs, format!("{}{}", self.vis, self.sig))
+        )
+    }
+}
+
+/// A named or tuple field of a struct, union or enum variant
+#[derive(Debug, Clone)]
+pub struct Field {
+    name: Option<String>,
+    vis: Vis,
+    ty: String,
+    docs: Option<String>,
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{}{}: {}", self.vis, name, self.ty),
+            None => write!(f, "{}{}", self.vis, self.ty),
+        }
+    }
+}
+
+/// A struct or union definition along with its fields
+#[derive(Debug, Clone)]
+pub struct Struct {
+    keyword: &'static str,
+    name: String,
+    vis: Vis,
+    generics: String,
+    where_preds: Vec<String>,
+    fields: Vec<Field>,
+    tuple: bool,
+    docs: Option<String>,
+}
+
+impl fmt::Display for Struct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = format!(
+            "{}{} {}{}",
+            self.vis, self.keyword, self.name, self.generics
+        );
+        // Private fields are left out as they are by rustdoc, leaving a placeholder comment
+        let public: Vec<&Field> = self.fields.iter().filter(|f| f.vis.is_pub()).collect();
+        let decl = if self.tuple {
+            let fields = if public.is_empty() {
+                vec![String::from("/* private fields */")]
+            } else {
+                self.fields
+                    .iter()
+                    .map(|f| {
+                        if f.vis.is_pub() {
+                            f.to_string()
+                        } else {
+                            String::from("/* private field */")
+                        }
+                    })
+                    .collect()
+            };
+            let head = format!("{}({})", head, fields.join(", "));
+            format!("{};", with_where(head, &self.where_preds))
+        } else if self.fields.is_empty() && self.keyword == "struct" {
+            format!("{};", with_where(head, &self.where_preds))
+        } else if public.is_empty() {
+            format!(
+                "{} /* private fields */ }}",
+                open_brace(with_where(head, &self.where_preds))
+            )
+        } else {
+            format!(
+                "{}{}",
+                open_brace(with_where(head, &self.where_preds)),
+                braced_fields(&public, public.len() < self.fields.len())
+            )
+        };
+
+        write!(f, "{}", with_docs(&self.docs, decl))
+    }
+}
+
+/// A single variant of an enum
+#[derive(Debug, Clone)]
+pub struct Variant {
+    name: String,
+    fields: Vec<Field>,
+    tuple: bool,
+    discriminant: Option<String>,
+    docs: Option<String>,
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = if self.fields.is_empty() {
+            String::new()
+        } else if self.tuple {
+            let fields = self
+                .fields
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>();
+            format!("({})", fields.join(", "))
+        } else {
+            let fields = self
+                .fields
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>();
+            format!(" {{ {} }}", fields.join(", "))
+        };
+
+        match &self.discriminant {
+            Some(d) => write!(f, "{}{} = {}", self.name, fields, d),
+            None => write!(f, "{}{}", self.name, fields),
+        }
+    }
+}
+
+/// An enum definition along with its variants
+#[derive(Debug, Clone)]
+pub struct Enum {
+    name: String,
+    vis: Vis,
+    generics: String,
+    where_preds: Vec<String>,
+    variants: Vec<Variant>,
+    docs: Option<String>,
+}
+
+impl fmt::Display for Enum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let head = format!("{}enum {}{}", self.vis, self.name, self.generics);
+        let mut decl = format!("{}\n", open_brace(with_where(head, &self.where_preds)));
+        for v in self.variants.iter() {
+            if let Some(docs) = &v.docs {
+                docs.lines()
+                    .for_each(|l| decl.push_str(&format!("    /// {}\n", l)));
+            }
+            decl.push_str(&format!("    {},\n", v));
+        }
+        decl.push('}');
+
+        write!(f, "{}", with_docs(&self.docs, decl))
+    }
+}
+
+/// A trait definition along with its required and provided items
+#[derive(Debug, Clone)]
+pub struct Trait {
+    name: String,
+    vis: Vis,
+    generics: String,
+    supertraits: String,
+    where_preds: Vec<String>,
+    required: Vec<Function>,
+    provided: Vec<Function>,
+    docs: Option<String>,
+}
+
+impl fmt::Display for Trait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let supertraits = if self.supertraits.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", self.supertraits)
+        };
+        let head = format!(
+            "{}trait {}{}{}",
+            self.vis, self.name, self.generics, supertraits
+        );
+        let mut decl = format!("{}\n", open_brace(with_where(head, &self.where_preds)));
+        for item in self.required.iter() {
+            decl.push_str(&indented(&format!("{};", item.sig)));
+        }
+        for item in self.provided.iter() {
+            decl.push_str(&indented(&format!("{} {{ ... }}", item.sig)));
+        }
+        decl.push('}');
+
+        write!(f, "{}", with_docs(&self.docs, decl))
+    }
+}
+
+/// An inherent or trait impl block
+#[derive(Debug, Clone)]
+pub struct Impl {
+    self_name: String,
+    self_ty: String,
+    trait_: Option<String>,
+    generics: String,
+    where_preds: Vec<String>,
+    items: Vec<Function>,
+    docs: Option<String>,
+}
+
+impl Impl {
+    fn header(&self) -> String {
+        let head = match &self.trait_ {
+            Some(t) => format!("impl{} {} for {}", self.generics, t, self.self_ty),
+            None => format!("impl{} {}", self.generics, self.self_ty),
+        };
+
+        with_where(head, &self.where_preds)
+    }
+}
+
+impl fmt::Display for Impl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let items = self
+            .items
+            .iter()
+            .map(|i| indented(&i.to_string()))
+            .collect::<String>();
+
+        write!(
+            f,
+            "{}\n{}",
+            with_docs(&self.docs, self.header()),
+            items.trim_end()
+        )
+    }
+}
+
+/// A const or static item
+#[derive(Debug, Clone)]
+pub struct Const {
+    keyword: &'static str,
+    name: String,
+    vis: Vis,
+    ty: String,
+    value: String,
+    docs: Option<String>,
+}
+
+impl fmt::Display for Const {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decl = format!(
+            "{}{} {}: {} = {};",
+            self.vis, self.keyword, self.name, self.ty, self.value
+        );
+        write!(f, "{}", with_docs(&self.docs, decl))
+    }
+}
+
+/// A type alias
+#[derive(Debug, Clone)]
+pub struct TypeAlias {
+    name: String,
+    vis: Vis,
+    generics: String,
+    ty: String,
+    docs: Option<String>,
+}
+
+impl fmt::Display for TypeAlias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let decl = format!(
+            "{}type {}{} = {};",
+            self.vis, self.name, self.generics, self.ty
+        );
+        write!(f, "{}", with_docs(&self.docs, decl))
+    }
+}
+
+/// A macro_rules! definition along with the matcher of each of its arms
+#[derive(Debug, Clone)]
+pub struct Macro {
+    name: String,
+    exported: bool,
+    arms: Vec<String>,
+    docs: Option<String>,
+}
+
+impl fmt::Display for Macro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut decl = if self.exported {
+            String::from("#[macro_export]\n")
         } else {
-            write!(f, "{}", self.sig)
+            String::new()
+        };
+        decl.push_str(&format!("macro_rules! {} {{\n", self.name));
+        for arm in self.arms.iter() {
+            decl.push_str(&format!("    {} => {{ ... }};\n", arm));
         }
+        decl.push('}');
+
+        write!(f, "{}", with_docs(&self.docs, decl))
     }
 }
 
+/// A single path brought into scope by a `use` declaration. Glob imports have no name.
+#[derive(Debug, Clone)]
+pub struct Import {
+    vis: Vis,
+    path: Vec<String>,
+    name: Option<String>,
+    hidden: bool,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct DocItems {
+    path: String,
     module: Module,
+    modules: Vec<Module>,
     fns: Vec<Function>,
+    structs: Vec<Struct>,
+    enums: Vec<Enum>,
+    traits: Vec<Trait>,
+    impls: Vec<Impl>,
+    consts: Vec<Const>,
+    statics: Vec<Const>,
+    types: Vec<TypeAlias>,
+    macros: Vec<Macro>,
+    imports: Vec<Import>,
+    // The full paths of the items marked #[doc(hidden)]
+    hidden: BTreeSet<String>,
+    aliases: Vec<(String, String)>,
+    locations: Vec<(String, Location)>,
 }
 
 impl DocItems {
+    /// The free function with the given name if there is one
+    pub fn function(&self, name: &str) -> Option<&Function> {
+        self.fns.iter().find(|f| f.name == name)
+    }
+
+    /// The names of items that have the given `#[doc(alias)]`
+    pub fn aliased(&self, alias: &str) -> Vec<&str> {
+        self.aliases
+            .iter()
+            .filter(|(a, _)| a == alias)
+            .map(|(_, name)| name.as_str())
+            .collect()
+    }
+
+    /// Render the named item: types also show their methods and implemented traits.
+    /// Names that are not found directly are looked up as doc aliases.
+    pub fn render_item(&self, name: &str) -> Option<String> {
+        if !self.item_names().any(|n| n == name) {
+            if let Some(real) = self.aliased(name).first() {
+                return self.render_item(real);
+            }
+        }
+
+        if let Some(f) = self.function(name) {
+            return Some(f.to_string());
+        }
+
+        let decl = None
+            .or_else(|| {
+                self.structs
+                    .iter()
+                    .find(|s| s.name == name)
+                    .map(|s| s.to_string())
+            })
+            .or_else(|| {
+                self.enums
+                    .iter()
+                    .find(|e| e.name == name)
+                    .map(|e| e.to_string())
+            })
+            .or_else(|| {
+                self.traits
+                    .iter()
+                    .find(|t| t.name == name)
+                    .map(|t| t.to_string())
+            })
+            .or_else(|| {
+                self.consts
+                    .iter()
+                    .find(|c| c.name == name)
+                    .map(|c| c.to_string())
+            })
+            .or_else(|| {
+                self.statics
+                    .iter()
+                    .find(|c| c.name == name)
+                    .map(|c| c.to_string())
+            })
+            .or_else(|| {
+                self.types
+                    .iter()
+                    .find(|t| t.name == name)
+                    .map(|t| t.to_string())
+            })
+            .or_else(|| {
+                self.macros
+                    .iter()
+                    .find(|m| m.name == name)
+                    .map(|m| m.to_string())
+            })
+            .or_else(|| {
+                self.modules
+                    .iter()
+                    .find(|m| m.name == name)
+                    .map(|m| m.to_string())
+            })?;
+
+        let mut sections = vec![decl];
+        let methods = self
+            .impls
+            .iter()
+            .filter(|i| i.self_name == name && i.trait_.is_none())
+            .flat_map(|i| i.items.iter().filter(|f| f.vis.is_pub()))
+            .map(|f| f.to_string())
+            .collect::<Vec<String>>();
+        if !methods.is_empty() {
+            sections.push(format!("[Methods]\n{}", methods.join("\n")));
+        }
+
+        let trait_impls = self
+            .impls
+            .iter()
+            .filter(|i| i.self_name == name && i.trait_.is_some())
+            .map(|i| i.header())
+            .collect::<Vec<String>>();
+        if !trait_impls.is_empty() {
+            sections.push(format!(
+                "[Trait Implementations]\n{}",
+                trait_impls.join("\n")
+            ));
+        }
+
+        Some(sections.join("\n\n"))
+    }
+
+    /// Render a method (or associated item) of the named type or trait
+    pub fn render_method(&self, type_name: &str, method: &str) -> Option<String> {
+        self.impls
+            .iter()
+            .filter(|i| i.self_name == type_name)
+            .flat_map(|i| i.items.iter())
+            .chain(
+                self.traits
+                    .iter()
+                    .filter(|t| t.name == type_name)
+                    .flat_map(|t| t.required.iter().chain(t.provided.iter())),
+            )
+            .find(|f| f.name == method)
+            .map(|f| f.to_string())
+    }
+
+    /// Whether this module defines a type (or trait) with the given name
+    fn defines_type(&self, name: &str) -> bool {
+        self.structs.iter().any(|s| s.name == name)
+            || self.enums.iter().any(|e| e.name == name)
+            || self.traits.iter().any(|t| t.name == name)
+            || self.types.iter().any(|t| t.name == name)
+    }
+
+    /// The names of every item defined directly in this module
+    fn item_names(&self) -> impl Iterator<Item = &str> {
+        self.items_with_pub().map(|(name, _)| name)
+    }
+
+    // Every item defined directly in this module along with whether it is `pub`. Macros
+    // count as `pub` if they are exported with #[macro_export].
+    fn items_with_pub(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.modules
+            .iter()
+            .map(|m| (m.name.as_str(), m.vis.is_pub()))
+            .chain(self.fns.iter().map(|f| (f.name.as_str(), f.vis.is_pub())))
+            .chain(
+                self.structs
+                    .iter()
+                    .map(|s| (s.name.as_str(), s.vis.is_pub())),
+            )
+            .chain(self.enums.iter().map(|e| (e.name.as_str(), e.vis.is_pub())))
+            .chain(
+                self.traits
+                    .iter()
+                    .map(|t| (t.name.as_str(), t.vis.is_pub())),
+            )
+            .chain(
+                self.consts
+                    .iter()
+                    .map(|c| (c.name.as_str(), c.vis.is_pub())),
+            )
+            .chain(
+                self.statics
+                    .iter()
+                    .map(|c| (c.name.as_str(), c.vis.is_pub())),
+            )
+            .chain(self.types.iter().map(|t| (t.name.as_str(), t.vis.is_pub())))
+            .chain(self.macros.iter().map(|m| (m.name.as_str(), m.exported)))
+    }
+
+    fn is_hidden(&self, name: &str) -> bool {
+        self.hidden.contains(&format!("{}::{}", self.path, name))
+    }
+
+    /// Where the named item is defined
+    pub fn location(&self, name: &str) -> Option<&Location> {
+        self.locations
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, loc)| loc)
+    }
+
+    /// Where a method (or associated item) of the named type or trait is defined
+    pub fn method_location(&self, type_name: &str, method: &str) -> Option<&Location> {
+        self.impls
+            .iter()
+            .filter(|i| i.self_name == type_name)
+            .flat_map(|i| i.items.iter())
+            .chain(
+                self.traits
+                    .iter()
+                    .filter(|t| t.name == type_name)
+                    .flat_map(|t| t.required.iter().chain(t.provided.iter())),
+            )
+            .find(|f| f.name == method)
+            .map(|f| &f.location)
+    }
+
+    fn structs_with_keyword(&self, keyword: &str) -> Vec<Struct> {
+        self.visible(&self.structs, |s| (&s.name, s.vis.is_pub()))
+            .into_iter()
+            .filter(|s| s.keyword == keyword)
+            .collect()
+    }
+
+    // The public items that are not marked as #[doc(hidden)]
+    fn visible<T: Clone>(&self, items: &[T], name_pub: impl Fn(&T) -> (&str, bool)) -> Vec<T> {
+        items
+            .iter()
+            .filter(|i| {
+                let (name, public) = name_pub(i);
+                public && !self.is_hidden(name)
+            })
+            .cloned()
+            .collect()
+    }
+
+    // Whether a type (or trait) defined in this module is private to the crate
+    fn is_private_type(&self, name: &str) -> bool {
+        self.defines_type(name) && !self.items_with_pub().any(|(n, public)| n == name && public)
+    }
+
+    // The impls of public types, keeping only the public methods of inherent impls.
+    // Items in trait impls have no visibility of their own and are always kept.
+    fn visible_impls(&self) -> Vec<Impl> {
+        self.impls
+            .iter()
+            .filter(|i| !self.is_private_type(&i.self_name))
+            .filter_map(|i| {
+                if i.trait_.is_some() {
+                    return Some(i.clone());
+                }
+                let items: Vec<Function> =
+                    i.items.iter().filter(|f| f.vis.is_pub()).cloned().collect();
+                match items.is_empty() {
+                    true => None,
+                    false => Some(Impl { items, ..i.clone() }),
+                }
+            })
+            .collect()
+    }
+
     pub fn render_all(&self) -> String {
-        format!(
-            "{}\n\n[Functions]\n{}",
-            self.module,
-            self.fns
-                .iter()
-                .map(|f| format!("{}\n", f))
-                .collect::<String>()
-        )
+        let sections = vec![
+            section(
+                "Modules",
+                &self.visible(&self.modules, |m| (&m.name, m.vis.is_pub())),
+            ),
+            section(
+                "Traits",
+                &self.visible(&self.traits, |t| (&t.name, t.vis.is_pub())),
+            ),
+            section(
+                "Constants",
+                &self.visible(&self.consts, |c| (&c.name, c.vis.is_pub())),
+            ),
+            section(
+                "Statics",
+                &self.visible(&self.statics, |c| (&c.name, c.vis.is_pub())),
+            ),
+            section("Structs", &self.structs_with_keyword("struct")),
+            section("Unions", &self.structs_with_keyword("union")),
+            section(
+                "Enums",
+                &self.visible(&self.enums, |e| (&e.name, e.vis.is_pub())),
+            ),
+            section(
+                "Type Definitions",
+                &self.visible(&self.types, |t| (&t.name, t.vis.is_pub())),
+            ),
+            section(
+                "Functions",
+                &self.visible(&self.fns, |f| (&f.name, f.vis.is_pub())),
+            ),
+            section(
+                "Macros",
+                &self.visible(&self.macros, |m| (&m.name, m.exported)),
+            ),
+            section("Implementations", &self.visible_impls()),
+        ];
+
+        let mut rendered = vec![match &self.module.docs {
+            Some(s) => format!("[{}]\n{}", self.module.name, s),
+            None => format!("[{}]", self.module.name),
+        }];
+        rendered.extend(sections.into_iter().flatten());
+        rendered.join("\n\n")
+    }
+}
+
+/// The items of every module in a crate, keyed by module path
+#[derive(Debug, Clone, Default)]
+pub struct CrateItems {
+    name: String,
+    modules: BTreeMap<String, DocItems>,
+    files: BTreeMap<String, PathBuf>,
+}
+
+impl CrateItems {
+    /// The name of the crate, which is also the path of its root module
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The extracted items of the module at the given path (e.g. "my_crate::foo::bar")
+    pub fn module(&self, path: &str) -> Option<&DocItems> {
+        self.modules.get(path)
+    }
+
+    /// The source file that the module at the given path was extracted from
+    pub fn file(&self, path: &str) -> Option<&Path> {
+        self.files.get(path).map(PathBuf::as_path)
+    }
+
+    /// Split a query path into the deepest module it names and the remaining components.
+    /// Paths that only exist through `pub use` re-exports are mapped back to the module
+    /// that defines the item.
+    pub fn resolve(&self, components: &[String]) -> Option<(String, Vec<String>)> {
+        let deepest = (1..=components.len())
+            .rev()
+            .find(|&i| self.modules.contains_key(&components[..i].join("::")));
+        if let Some(i) = deepest {
+            let path = components[..i].join("::");
+            let defined = match components.get(i) {
+                Some(name) => self.modules[&path].item_names().any(|n| n == name),
+                None => true,
+            };
+            if defined {
+                return Some((path, components[i..].to_vec()));
+            }
+        }
+
+        let api = self.public_api();
+        (1..=components.len()).rev().find_map(|i| {
+            let def = api.get(&components[..i].join("::"))?;
+            if self.modules.contains_key(def) {
+                return Some((def.clone(), components[i..].to_vec()));
+            }
+            let (module, name) = def.rsplit_once("::")?;
+            let mut remaining = vec![name.to_string()];
+            remaining.extend(components[i..].iter().cloned());
+            Some((module.to_string(), remaining))
+        })
+    }
+
+    /**
+     * The public API of the crate as rustdoc would publish it: a map from each public item
+     * path to the path of the module item that defines it. Items are public if they are
+     * `pub` and reachable through `pub` modules or `pub use` re-exports (including globs)
+     * from the crate root. `#[doc(hidden)]` items and exported macros are handled as they
+     * are by rustdoc.
+     */
+    pub fn public_api(&self) -> BTreeMap<String, String> {
+        let mut api = BTreeMap::new();
+        let mut visited = BTreeSet::new();
+        self.public_items(&self.name, &self.name, &mut api, &mut visited);
+
+        // #[macro_export] places macros at the crate root regardless of where they are defined
+        for (path, items) in self.modules.iter() {
+            for m in items.macros.iter().filter(|m| m.exported) {
+                if !items.is_hidden(&m.name) {
+                    api.insert(
+                        format!("{}::{}", self.name, m.name),
+                        format!("{}::{}", path, m.name),
+                    );
+                }
+            }
+        }
+
+        api
+    }
+
+    // Add the public contents of the module defined at `def` to the API under `public`
+    fn public_items(
+        &self,
+        public: &str,
+        def: &str,
+        api: &mut BTreeMap<String, String>,
+        visited: &mut BTreeSet<(String, String)>,
+    ) {
+        if !visited.insert((public.to_string(), def.to_string())) {
+            return;
+        }
+        let items = match self.modules.get(def) {
+            Some(items) => items,
+            None => return,
+        };
+
+        for (name, is_pub) in items.items_with_pub() {
+            if !is_pub || items.is_hidden(name) || items.macros.iter().any(|m| m.name == name) {
+                continue;
+            }
+            let (public_path, def_path) = (
+                format!("{}::{}", public, name),
+                format!("{}::{}", def, name),
+            );
+            if self.modules.contains_key(&def_path) {
+                self.public_items(&public_path, &def_path, api, visited);
+            }
+            api.insert(public_path, def_path);
+        }
+
+        for import in items.imports.iter().filter(|i| i.vis.is_pub() && !i.hidden) {
+            let target = self.import_target(def, &import.path);
+            match &import.name {
+                // Glob re-exports of external crates can't be resolved from source
+                None => {
+                    if let Some(target) = target {
+                        self.public_items(public, &target, api, visited);
+                    }
+                }
+                Some(name) => {
+                    let public_path = format!("{}::{}", public, name);
+                    let def_path = match target {
+                        Some(target) => self.definition(&target, 0).unwrap_or(target),
+                        None => import.path.join("::"),
+                    };
+                    // Only `pub` items can be re-exported outside the crate
+                    if !self.is_public_definition(&def_path) {
+                        continue;
+                    }
+                    if self.modules.contains_key(&def_path) {
+                        self.public_items(&public_path, &def_path, api, visited);
+                    }
+                    api.insert(public_path, def_path);
+                }
+            }
+        }
+    }
+
+    // Whether the item at `path` is declared `pub` in its module. Paths that aren't items of
+    // a known module (other crates, enum variants) can't be checked and are assumed public.
+    fn is_public_definition(&self, path: &str) -> bool {
+        let (module, name) = match path.rsplit_once("::") {
+            Some(split) => split,
+            None => return true,
+        };
+        match self.modules.get(module) {
+            Some(items) => {
+                items
+                    .items_with_pub()
+                    .any(|(n, public)| n == name && public)
+                    || items
+                        .imports
+                        .iter()
+                        .any(|i| i.name.as_deref() == Some(name) && i.vis.is_pub())
+            }
+            None => true,
+        }
+    }
+
+    // Resolve the path of a `use` declaration in the module `def` to an absolute path
+    // within this crate. Returns None for paths into other crates.
+    fn import_target(&self, def: &str, path: &[String]) -> Option<String> {
+        let mut module: Vec<&str> = def.split("::").collect();
+        let mut components = path.iter().peekable();
+
+        match components.peek().map(|c| c.as_str()) {
+            Some("crate") => {
+                module.truncate(1);
+                components.next();
+            }
+            Some("self") => {
+                components.next();
+            }
+            Some("super") => {
+                while components.peek().map(|c| c.as_str()) == Some("super") {
+                    module.pop();
+                    components.next();
+                }
+            }
+            Some(first) if first == self.name => {
+                module.truncate(1);
+                components.next();
+            }
+            Some(first) => {
+                let local = self.modules.get(def).is_some_and(|items| {
+                    items.item_names().any(|n| n == first)
+                        || items
+                            .imports
+                            .iter()
+                            .any(|i| i.name.as_deref() == Some(first))
+                });
+                if !local {
+                    return None;
+                }
+            }
+            None => return None,
+        }
+
+        if module.is_empty() {
+            return None;
+        }
+        let mut target: Vec<String> = module.iter().map(|s| s.to_string()).collect();
+        target.extend(components.cloned());
+
+        Some(target.join("::"))
+    }
+
+    // Follow `use` declarations from an item path to the module that defines the item
+    fn definition(&self, path: &str, depth: usize) -> Option<String> {
+        if self.modules.contains_key(path) {
+            return Some(path.to_string());
+        }
+        let (module, name) = path.rsplit_once("::")?;
+        let items = self.modules.get(module)?;
+        if items.item_names().any(|n| n == name) {
+            return Some(path.to_string());
+        }
+        if depth > 16 {
+            return None;
+        }
+
+        let import = items
+            .imports
+            .iter()
+            .find(|i| i.name.as_deref() == Some(name))?;
+        let target = self.import_target(module, &import.path)?;
+        self.definition(&target, depth + 1)
+    }
+
+    /// The full path of every module and item in the crate
+    pub fn item_paths(&self) -> Vec<String> {
+        self.modules
+            .iter()
+            .flat_map(|(path, items)| {
+                let mut paths = vec![path.clone()];
+                paths.extend(items.item_names().map(|name| format!("{}::{}", path, name)));
+                paths
+            })
+            .collect()
+    }
+
+    /// Where the item named by a query path is defined. Modules with their own file are
+    /// located at the start of that file.
+    pub fn location(&self, components: &[String]) -> Option<Location> {
+        let (module, remaining) = self.resolve(components)?;
+        let items = self.modules.get(&module)?;
+
+        match remaining.as_slice() {
+            [] => {
+                let file = self.files.get(&module)?;
+                let declared = module.rsplit_once("::").and_then(|(parent, name)| {
+                    if self.files.get(parent) == Some(file) {
+                        self.modules.get(parent)?.location(name).cloned()
+                    } else {
+                        None
+                    }
+                });
+
+                let n_lines = fs::read_to_string(file).ok()?.lines().count();
+                Some(declared.unwrap_or(Location {
+                    file: file.clone(),
+                    line: 1,
+                    column: 1,
+                    lines: 1..=n_lines.max(1),
+                }))
+            }
+            [name] => items.location(name).cloned(),
+            [ty, method] => items.method_location(ty, method).cloned(),
+            _ => None,
+        }
+    }
+
+    /// Every `#[doc(alias)]` in the crate along with the path of the item it is for
+    pub fn aliases(&self) -> Vec<(String, String)> {
+        self.modules
+            .iter()
+            .flat_map(|(path, items)| {
+                items
+                    .aliases
+                    .iter()
+                    .map(move |(alias, name)| (alias.clone(), format!("{}::{}", path, name)))
+            })
+            .collect()
+    }
+
+    // Follow the child modules of a parsed module, extracting their items as we go
+    fn walk_module(
+        &mut self,
+        path: String,
+        file: &Path,
+        mod_dir: &Path,
+        inline: bool,
+        attrs: &[Attribute],
+        items: &[Item],
+    ) {
+        let (doc_items, child_mods) = extract_module_items(&path, file, attrs, items);
+        self.modules.insert(path.clone(), doc_items);
+        self.files.insert(path.clone(), file.to_path_buf());
+
+        for m in child_mods.iter() {
+            let child_path = format!("{}::{}", path, m.ident);
+            let child_dir = mod_dir.join(m.ident.to_string());
+
+            if let Some((_, items)) = &m.content {
+                self.walk_module(child_path, file, &child_dir, true, &m.attrs, items);
+                continue;
+            }
+
+            let (child_file, child_dir) = match path_attribute(&m.attrs) {
+                Some(p) => {
+                    let base = if inline {
+                        mod_dir
+                    } else {
+                        file.parent().unwrap_or(mod_dir)
+                    };
+                    let child_file = base.join(p);
+                    let dir = child_file
+                        .parent()
+                        .map(Path::to_path_buf)
+                        .unwrap_or_default();
+                    (child_file, dir)
+                }
+                None => {
+                    let flat = mod_dir.join(format!("{}.rs", m.ident));
+                    if flat.is_file() {
+                        (flat, child_dir)
+                    } else {
+                        (child_dir.join("mod.rs"), child_dir)
+                    }
+                }
+            };
+
+            // Missing or unparsable module files are skipped rather than failing the whole crate
+            let syntax = match fs::read_to_string(&child_file).map(|c| syn::parse_file(&c)) {
+                Ok(Ok(syntax)) => syntax,
+                _ => continue,
+            };
+
+            let mut child_attrs = m.attrs.clone();
+            child_attrs.extend(syntax.attrs.iter().cloned());
+            self.walk_module(
+                child_path,
+                &child_file,
+                &child_dir,
+                false,
+                &child_attrs,
+                &syntax.items,
+            );
+        }
+    }
+
+    // Move impl blocks onto the module that defines their self type when that is elsewhere
+    fn stitch_impls(&mut self) {
+        let paths: Vec<String> = self.modules.keys().cloned().collect();
+
+        for path in paths.iter() {
+            let impls = std::mem::take(&mut self.modules.get_mut(path).unwrap().impls);
+            for i in impls {
+                let defined_here = self.modules[path].defines_type(&i.self_name);
+                let defining: Vec<&String> = paths
+                    .iter()
+                    .filter(|p| self.modules[*p].defines_type(&i.self_name))
+                    .collect();
+
+                let target = match defining.as_slice() {
+                    [p] if !defined_here => (*p).clone(),
+                    _ => path.clone(),
+                };
+                self.modules.get_mut(&target).unwrap().impls.push(i);
+            }
+        }
     }
 }
 
 #[derive(Debug, Clone, Default)]
 struct Extractor {
-    module: String,
+    file: PathBuf,
+    dir: PathBuf,
     items: DocItems,
+    child_mods: Vec<ItemMod>,
 }
 
 pub fn extract_items(module: &str, contents: &str) -> Result<DocItems> {
     let syntax = syn::parse_file(contents)?;
+    let (items, _) = extract_module_items(module, Path::new(""), &syntax.attrs, &syntax.items);
+
+    Ok(items)
+}
+
+/**
+ * Extract the items of an entire crate starting from its root source file (lib.rs or
+ * main.rs), following `mod` declarations into other files.
+ */
+pub fn extract_crate(name: &str, root: &Path) -> io::Result<CrateItems> {
+    let contents = fs::read_to_string(root)?;
+    let syntax =
+        syn::parse_file(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mod_dir = root.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let mut krate = CrateItems {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    krate.walk_module(
+        name.to_string(),
+        root,
+        &mod_dir,
+        false,
+        &syntax.attrs,
+        &syntax.items,
+    );
+    krate.stitch_impls();
+
+    Ok(krate)
+}
+
+// Extract the items of the module at the given path, along with its child module declarations
+fn extract_module_items(
+    path: &str,
+    file: &Path,
+    attrs: &[Attribute],
+    items: &[Item],
+) -> (DocItems, Vec<ItemMod>) {
+    let dir = match file.parent() {
+        Some(dir) if dir != Path::new("") => dir,
+        _ => Path::new("."),
+    };
     let mut ex = Extractor {
-        module: module.to_string(),
-        items: Default::default(),
+        file: file.to_path_buf(),
+        dir: dir.to_path_buf(),
+        items: DocItems {
+            path: path.to_string(),
+            ..Default::default()
+        },
+        child_mods: vec![],
     };
-    ex.visit_file(&syntax);
 
-    Ok(ex.items)
+    ex.items.module = Module {
+        name: path.rsplit("::").next().unwrap_or(path).to_string(),
+        vis: Vis::Public,
+        docs: extract_docs(attrs, dir),
+    };
+    for item in items.iter() {
+        if let Some((attrs, ident)) = item_attrs(item) {
+            let name = ident.to_string();
+            let location = ex.location(ident.span(), attrs, item);
+            ex.items.locations.push((name.clone(), location));
+            for attr in attrs.iter().flat_map(|a| doc_attrs(a, dir)) {
+                match attr {
+                    DocAttr::Hidden => {
+                        ex.items.hidden.insert(format!("{}::{}", path, name));
+                    }
+                    DocAttr::Alias(alias) => ex.items.aliases.push((alias, name.clone())),
+                    DocAttr::Text(_) => (),
+                }
+            }
+        }
+        ex.visit_item(item);
+    }
+
+    (ex.items, ex.child_mods)
 }
 
-impl<'ast> Visit<'ast> for Extractor {
-    fn visit_file(&mut self, node: &'ast File) {
-        self.items.module = Module {
-            name: self.module.clone(),
-            docs: extract_docs(&node.attrs).unwrap_or_default(),
-        };
+impl Extractor {
+    // The location of an item's name along with the lines spanned by the item itself,
+    // starting from its first attribute that isn't a doc comment
+    fn location<T: Spanned>(&self, ident: Span, attrs: &[Attribute], item: &T) -> Location {
+        let start = ident.start();
+        let first = attrs
+            .iter()
+            .filter(|a| !a.path.is_ident("doc"))
+            .map(|a| a.span().start().line)
+            .fold(start.line, usize::min);
 
-        visit::visit_file(self, node);
+        Location {
+            file: self.file.clone(),
+            line: start.line,
+            column: start.column + 1,
+            lines: first..=item.span().end().line,
+        }
     }
+}
 
+impl<'ast> Visit<'ast> for Extractor {
     fn visit_item_fn(&mut self, node: &'ast ItemFn) {
         self.items.fns.push(Function {
-            // name: node.sig.ident.to_string(),
-            // vis: format!("{}", node.vis.clone().into_token_stream()),
-            sig: format_sig(node.sig.clone()),
-            docs: extract_docs(&node.attrs).unwrap_or_default(),
+            name: node.sig.ident.to_string(),
+            location: self.location(node.sig.ident.span(), &node.attrs, node),
+            vis: format_vis(&node.vis),
+            sig: format_sig(&node.sig, format_vis(&node.vis).to_string().len()),
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    // Child modules are recorded but their contents are not visited here
+    fn visit_item_mod(&mut self, node: &'ast ItemMod) {
+        self.items.modules.push(Module {
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+        self.child_mods.push(node.clone());
+    }
+
+    fn visit_item_use(&mut self, node: &'ast ItemUse) {
+        let mut imports = vec![];
+        flatten_use_tree(&node.tree, vec![], &mut imports);
+
+        let vis = format_vis(&node.vis);
+        let hidden = is_doc_hidden(&node.attrs, &self.dir);
+        self.items
+            .imports
+            .extend(imports.into_iter().map(|(path, name)| Import {
+                vis: vis.clone(),
+                path,
+                name,
+                hidden,
+            }));
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
+        let (fields, tuple) = extract_fields(&node.fields, &self.dir);
+        self.items.structs.push(Struct {
+            keyword: "struct",
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            generics: format_generics(&node.generics),
+            where_preds: signature::where_predicates(&node.generics.where_clause),
+            fields,
+            tuple,
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    fn visit_item_union(&mut self, node: &'ast ItemUnion) {
+        let (fields, _) = extract_fields(&Fields::Named(node.fields.clone()), &self.dir);
+        self.items.structs.push(Struct {
+            keyword: "union",
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            generics: format_generics(&node.generics),
+            where_preds: signature::where_predicates(&node.generics.where_clause),
+            fields,
+            tuple: false,
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+        let variants = node
+            .variants
+            .iter()
+            .filter(|v| !is_doc_hidden(&v.attrs, &self.dir))
+            .map(|v| {
+                let (fields, tuple) = extract_fields(&v.fields, &self.dir);
+                Variant {
+                    name: v.ident.to_string(),
+                    fields,
+                    tuple,
+                    discriminant: v.discriminant.as_ref().map(|(_, e)| format_tokens(e)),
+                    docs: extract_docs(&v.attrs, &self.dir),
+                }
+            })
+            .collect();
+
+        self.items.enums.push(Enum {
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            generics: format_generics(&node.generics),
+            where_preds: signature::where_predicates(&node.generics.where_clause),
+            variants,
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    fn visit_item_trait(&mut self, node: &'ast ItemTrait) {
+        let mut required = vec![];
+        let mut provided = vec![];
+
+        for item in node.items.iter() {
+            let (f, has_default) = match item {
+                TraitItem::Method(m) if is_doc_hidden(&m.attrs, &self.dir) => continue,
+                TraitItem::Const(c) if is_doc_hidden(&c.attrs, &self.dir) => continue,
+                TraitItem::Type(t) if is_doc_hidden(&t.attrs, &self.dir) => continue,
+                TraitItem::Method(m) => (
+                    Function {
+                        name: m.sig.ident.to_string(),
+                        location: self.location(m.sig.ident.span(), &m.attrs, m),
+                        vis: Vis::Inherited,
+                        sig: format_sig(&m.sig, INDENT.len()),
+                        docs: extract_docs(&m.attrs, &self.dir),
+                    },
+                    m.default.is_some(),
+                ),
+                TraitItem::Const(c) => (
+                    Function {
+                        name: c.ident.to_string(),
+                        location: self.location(c.ident.span(), &c.attrs, c),
+                        vis: Vis::Inherited,
+                        sig: format!("const {}: {}", c.ident, format_tokens(&c.ty)),
+                        docs: extract_docs(&c.attrs, &self.dir),
+                    },
+                    c.default.is_some(),
+                ),
+                TraitItem::Type(t) => {
+                    let bounds = if t.bounds.is_empty() {
+                        String::new()
+                    } else {
+                        format!(": {}", format_tokens(&t.bounds))
+                    };
+                    (
+                        Function {
+                            name: t.ident.to_string(),
+                            location: self.location(t.ident.span(), &t.attrs, t),
+                            vis: Vis::Inherited,
+                            sig: format!("type {}{}", t.ident, bounds),
+                            docs: extract_docs(&t.attrs, &self.dir),
+                        },
+                        t.default.is_some(),
+                    )
+                }
+                _ => continue,
+            };
+
+            if has_default {
+                provided.push(f);
+            } else {
+                required.push(f);
+            }
+        }
+
+        self.items.traits.push(Trait {
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            generics: format_generics(&node.generics),
+            supertraits: format_tokens(&node.supertraits),
+            where_preds: signature::where_predicates(&node.generics.where_clause),
+            required,
+            provided,
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        let items = node
+            .items
+            .iter()
+            .filter_map(|item| match item {
+                ImplItem::Method(m) if is_doc_hidden(&m.attrs, &self.dir) => None,
+                ImplItem::Const(c) if is_doc_hidden(&c.attrs, &self.dir) => None,
+                ImplItem::Type(t) if is_doc_hidden(&t.attrs, &self.dir) => None,
+                ImplItem::Method(m) => Some(Function {
+                    name: m.sig.ident.to_string(),
+                    location: self.location(m.sig.ident.span(), &m.attrs, m),
+                    vis: format_vis(&m.vis),
+                    sig: format_sig(&m.sig, INDENT.len() + format_vis(&m.vis).to_string().len()),
+                    docs: extract_docs(&m.attrs, &self.dir),
+                }),
+                ImplItem::Const(c) => Some(Function {
+                    name: c.ident.to_string(),
+                    location: self.location(c.ident.span(), &c.attrs, c),
+                    vis: format_vis(&c.vis),
+                    sig: format!(
+                        "const {}: {} = {}",
+                        c.ident,
+                        format_tokens(&c.ty),
+                        format_tokens(&c.expr)
+                    ),
+                    docs: extract_docs(&c.attrs, &self.dir),
+                }),
+                ImplItem::Type(t) => Some(Function {
+                    name: t.ident.to_string(),
+                    location: self.location(t.ident.span(), &t.attrs, t),
+                    vis: format_vis(&t.vis),
+                    sig: format!("type {} = {}", t.ident, format_tokens(&t.ty)),
+                    docs: extract_docs(&t.attrs, &self.dir),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        self.items.impls.push(Impl {
+            self_name: type_name(&node.self_ty),
+            self_ty: format_tokens(&node.self_ty),
+            trait_: node.trait_.as_ref().map(|(bang, path, _)| {
+                format!(
+                    "{}{}",
+                    if bang.is_some() { "!" } else { "" },
+                    format_tokens(path)
+                )
+            }),
+            generics: format_generics(&node.generics),
+            where_preds: signature::where_predicates(&node.generics.where_clause),
+            items,
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    fn visit_item_const(&mut self, node: &'ast ItemConst) {
+        self.items.consts.push(Const {
+            keyword: "const",
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            ty: format_tokens(&node.ty),
+            value: format_tokens(&node.expr),
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    fn visit_item_static(&mut self, node: &'ast ItemStatic) {
+        self.items.statics.push(Const {
+            keyword: if node.mutability.is_some() {
+                "static mut"
+            } else {
+                "static"
+            },
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            ty: format_tokens(&node.ty),
+            value: format_tokens(&node.expr),
+            docs: extract_docs(&node.attrs, &self.dir),
+        });
+    }
+
+    fn visit_item_type(&mut self, node: &'ast ItemType) {
+        self.items.types.push(TypeAlias {
+            name: node.ident.to_string(),
+            vis: format_vis(&node.vis),
+            generics: format_generics(&node.generics),
+            ty: format_tokens(&node.ty),
+            docs: extract_docs(&node.attrs, &self.dir),
         });
     }
+
+    fn visit_item_macro(&mut self, node: &'ast ItemMacro) {
+        // Only macro_rules! definitions have a name: other items are macro invocations
+        if let Some(ident) = &node.ident {
+            self.items.macros.push(Macro {
+                name: ident.to_string(),
+                exported: node.attrs.iter().any(|a| a.path.is_ident("macro_export")),
+                arms: macro_arms(&node.mac.tokens),
+                docs: extract_docs(&node.attrs, &self.dir),
+            });
+        }
+    }
 }
 
-fn extract_docs(attrs: &[Attribute]) -> Result<Option<String>> {
-    let mut docs = vec![];
+fn extract_fields(fields: &Fields, dir: &Path) -> (Vec<Field>, bool) {
+    let extracted = fields
+        .iter()
+        .filter(|f| !is_doc_hidden(&f.attrs, dir))
+        .map(|f| Field {
+            name: f.ident.as_ref().map(|i| i.to_string()),
+            vis: format_vis(&f.vis),
+            ty: format_tokens(&f.ty),
+            docs: extract_docs(&f.attrs, dir),
+        })
+        .collect();
 
-    for attr in attrs {
-        match attr.parse_meta()? {
-            Meta::NameValue(nv) if nv.path.is_ident("doc") => {
-                if let Lit::Str(s) = nv.lit {
-                    docs.push(s.value().trim().to_string());
+    (extracted, matches!(fields, Fields::Unnamed(_)))
+}
+
+// The matchers of each `(...) => { ... };` arm in the body of a macro_rules! definition
+fn macro_arms(tokens: &TokenStream) -> Vec<String> {
+    let trees: Vec<TokenTree> = tokens.clone().into_iter().collect();
+    let mut arms = vec![];
+
+    for (i, tree) in trees.iter().enumerate() {
+        if let TokenTree::Group(_) = tree {
+            let followed_by_arrow = matches!(
+                (trees.get(i + 1), trees.get(i + 2)),
+                (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+                    if a.as_char() == '=' && b.as_char() == '>'
+            );
+            if followed_by_arrow {
+                arms.push(format_matcher(TokenStream::from(tree.clone())));
+            }
+        }
+    }
+
+    arms
+}
+
+/**
+ * Render the matcher of a macro arm as rustdoc does: metavariables are written without
+ * spaces (`$e:expr`) and the separator and operator of a repetition are attached to it
+ * (`$($x:tt),*`).
+ */
+fn format_matcher(tokens: TokenStream) -> String {
+    let mut s = String::new();
+    // Whether the next token follows the previous one without a space
+    let mut attach = true;
+    // The previous token was `$`, an `$ident` metavariable or a `$(...)` repetition
+    let (mut dollar, mut metavar, mut repetition) = (false, false, false);
+
+    for tree in tokens {
+        let punct = match &tree {
+            TokenTree::Punct(p) => Some(p.as_char()),
+            _ => None,
+        };
+        let text = match &tree {
+            TokenTree::Group(g) => {
+                let inner = format_matcher(g.stream());
+                match g.delimiter() {
+                    Delimiter::Parenthesis => format!("({})", inner),
+                    Delimiter::Bracket => format!("[{}]", inner),
+                    Delimiter::Brace if inner.is_empty() => String::from("{}"),
+                    Delimiter::Brace => format!("{{ {} }}", inner),
+                    Delimiter::None => inner,
                 }
             }
-            _ => continue,
+            _ => tree.to_string(),
+        };
+
+        let fragment = metavar && punct == Some(':');
+        if !(attach || fragment || repetition || matches!(punct, Some(',') | Some(';'))) {
+            s.push(' ');
         }
+        s.push_str(&text);
+
+        attach = fragment
+            || punct == Some('$')
+            || matches!(&tree, TokenTree::Punct(p) if p.spacing() == Spacing::Joint);
+        metavar = dollar && matches!(tree, TokenTree::Ident(_));
+        // A separator may come between a repetition and its operator
+        repetition = (dollar && matches!(tree, TokenTree::Group(_)))
+            || (repetition && !matches!(punct, None | Some('*') | Some('+') | Some('?')));
+        dollar = punct == Some('$');
+    }
+
+    s
+}
+
+// The last path segment of a type, used to match impl blocks to the type they are for
+fn type_name(ty: &Type) -> String {
+    match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default(),
+        Type::Reference(r) => type_name(&r.elem),
+        _ => format_tokens(ty),
     }
+}
 
-    Ok(if docs.is_empty() {
+// The fields of a struct or union, one per line, and the closing brace
+fn braced_fields(fields: &[&Field], private: bool) -> String {
+    let mut s = String::from("\n");
+    for field in fields.iter() {
+        if let Some(docs) = &field.docs {
+            docs.lines()
+                .for_each(|l| s.push_str(&format!("    /// {}\n", l)));
+        }
+        s.push_str(&format!("    {},\n", field));
+    }
+    if private {
+        s.push_str("    /* private fields */\n");
+    }
+    s.push('}');
+    s
+}
+
+fn section<T: fmt::Display>(title: &str, items: &[T]) -> Option<String> {
+    if items.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "[{}]\n{}",
+        title,
+        items
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>()
+            .join("\n\n")
+    ))
+}
+
+fn with_docs(docs: &Option<String>, decl: String) -> String {
+    match docs {
+        Some(s) => format!("{}\n{}", s, decl),
+        None => decl,
+    }
+}
+
+// The documentation text of an item, with `include_str!` paths resolved relative to `dir`
+fn extract_docs(attrs: &[Attribute], dir: &Path) -> Option<String> {
+    let docs: Vec<String> = attrs
+        .iter()
+        .flat_map(|a| doc_attrs(a, dir))
+        .filter_map(|attr| match attr {
+            DocAttr::Text(s) => Some(s),
+            _ => None,
+        })
+        .collect();
+
+    if docs.is_empty() {
         None
     } else {
         Some(docs.join("\n"))
+    }
+}
+
+// Each path imported by a use tree along with the name it is imported as
+pub(crate) fn flatten_use_tree(
+    tree: &UseTree,
+    prefix: Vec<String>,
+    out: &mut Vec<(Vec<String>, Option<String>)>,
+) {
+    match tree {
+        UseTree::Path(p) => {
+            let mut prefix = prefix;
+            prefix.push(p.ident.to_string());
+            flatten_use_tree(&p.tree, prefix, out);
+        }
+        UseTree::Name(n) if n.ident == "self" => {
+            if let Some(name) = prefix.last().cloned() {
+                out.push((prefix, Some(name)));
+            }
+        }
+        UseTree::Name(n) => {
+            let mut path = prefix;
+            path.push(n.ident.to_string());
+            out.push((path, Some(n.ident.to_string())));
+        }
+        UseTree::Rename(r) => {
+            let mut path = prefix;
+            if r.ident != "self" {
+                path.push(r.ident.to_string());
+            }
+            out.push((path, Some(r.rename.to_string())));
+        }
+        UseTree::Glob(_) => out.push((prefix, None)),
+        UseTree::Group(g) => g
+            .items
+            .iter()
+            .for_each(|t| flatten_use_tree(t, prefix.clone(), out)),
+    }
+}
+
+// The attributes and identifier of an item that can carry its own documentation
+pub(crate) fn item_attrs(item: &Item) -> Option<(&[Attribute], &Ident)> {
+    let (attrs, ident) = match item {
+        Item::Const(i) => (&i.attrs, &i.ident),
+        Item::Enum(i) => (&i.attrs, &i.ident),
+        Item::Fn(i) => (&i.attrs, &i.sig.ident),
+        Item::Mod(i) => (&i.attrs, &i.ident),
+        Item::Static(i) => (&i.attrs, &i.ident),
+        Item::Struct(i) => (&i.attrs, &i.ident),
+        Item::Trait(i) => (&i.attrs, &i.ident),
+        Item::Type(i) => (&i.attrs, &i.ident),
+        Item::Union(i) => (&i.attrs, &i.ident),
+        Item::Macro(i) => (&i.attrs, i.ident.as_ref()?),
+        _ => return None,
+    };
+
+    Some((attrs, ident))
+}
+
+fn is_doc_hidden(attrs: &[Attribute], dir: &Path) -> bool {
+    attrs
+        .iter()
+        .flat_map(|a| doc_attrs(a, dir))
+        .any(|attr| matches!(attr, DocAttr::Hidden))
+}
+
+/// The parts of a doc attribute that we make use of
+#[derive(Debug, Clone, PartialEq)]
+enum DocAttr {
+    Text(String),
+    Alias(String),
+    Hidden,
+}
+
+/**
+ * Interpret a single attribute as documentation. This covers `#[doc = "..."]`,
+ * `#[doc = include_str!("...")]` (resolved relative to `dir`), `#[doc(alias = "...")]`,
+ * `#[doc(hidden)]` and any of these wrapped in `#[cfg_attr(..., ...)]`. Anything else,
+ * including attributes that are not valid meta items, is ignored.
+ */
+fn doc_attrs(attr: &Attribute, dir: &Path) -> Vec<DocAttr> {
+    doc_attr_body(&attr.path, attr.tokens.clone(), dir)
+}
+
+fn doc_attr_body(path: &syn::Path, tokens: TokenStream, dir: &Path) -> Vec<DocAttr> {
+    if path.is_ident("cfg_attr") {
+        return cfg_attr_body(tokens)
+            .map(|attrs| {
+                attrs
+                    .into_iter()
+                    .flat_map(|(p, t)| doc_attr_body(&p, t, dir))
+                    .collect()
+            })
+            .unwrap_or_default();
+    } else if !path.is_ident("doc") {
+        return vec![];
+    }
+
+    let value = |input: ParseStream| -> Result<Expr> {
+        input.parse::<Token![=]>()?;
+        input.parse()
+    };
+    if let Ok(expr) = value.parse2(tokens.clone()) {
+        return doc_text(&expr, dir)
+            .map(DocAttr::Text)
+            .into_iter()
+            .collect();
+    }
+
+    let nested = match tokens.into_iter().next() {
+        Some(TokenTree::Group(g)) => Punctuated::<NestedMeta, Token![,]>::parse_terminated
+            .parse2(g.stream())
+            .unwrap_or_default(),
+        _ => return vec![],
+    };
+
+    nested
+        .iter()
+        .flat_map(|n| match n {
+            NestedMeta::Meta(Meta::Path(p)) if p.is_ident("hidden") => vec![DocAttr::Hidden],
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("alias") => match &nv.lit {
+                Lit::Str(s) => vec![DocAttr::Alias(s.value())],
+                _ => vec![],
+            },
+            NestedMeta::Meta(Meta::List(l)) if l.path.is_ident("alias") => l
+                .nested
+                .iter()
+                .filter_map(|n| match n {
+                    NestedMeta::Lit(Lit::Str(s)) => Some(DocAttr::Alias(s.value())),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        })
+        .collect()
+}
+
+// Split `(predicate, attr, attr...)` into the paths and remaining tokens of each attr
+fn cfg_attr_body(tokens: TokenStream) -> Result<Vec<(syn::Path, TokenStream)>> {
+    let group = match tokens.into_iter().next() {
+        Some(TokenTree::Group(g)) => g,
+        _ => return Ok(vec![]),
+    };
+
+    let parser = |input: ParseStream| {
+        input.parse::<NestedMeta>()?;
+        let mut attrs = vec![];
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let path: syn::Path = input.parse()?;
+            let mut rest = TokenStream::new();
+            while !input.is_empty() && !input.peek(Token![,]) {
+                rest.extend(std::iter::once(input.parse::<TokenTree>()?));
+            }
+            attrs.push((path, rest));
+        }
+        Ok(attrs)
+    };
+
+    parser.parse2(group.stream())
+}
+
+// The text of a `doc = ...` value: either a string literal or an include_str! invocation
+fn doc_text(expr: &Expr, dir: &Path) -> Option<String> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Some(s.value().trim().to_string()),
+        Expr::Macro(m) if m.mac.path.is_ident("include_str") => {
+            let file: LitStr = m.mac.parse_body().ok()?;
+            fs::read_to_string(dir.join(file.value()))
+                .ok()
+                .map(|s| s.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+// The target of a `#[path = "..."]` attribute on a module declaration
+fn path_attribute(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| match attr.parse_meta() {
+        Ok(Meta::NameValue(nv)) if nv.path.is_ident("path") => match nv.lit {
+            Lit::Str(s) => Some(s.value()),
+            _ => None,
+        },
+        _ => None,
     })
 }
 
-fn format_sig(sig: Signature) -> String {
-    format!("{}", sig.into_token_stream())
-        .replace(" (", "(")
-        .replace(" < ", "<")
-        .replace(" > ", ">")
-        .replace(" >", ">")
-        .replace("& ", "&")
-        .replace(" ,", ",")
-        .replace(" :", ":")
+fn format_vis(vis: &Visibility) -> Vis {
+    match vis {
+        Visibility::Public(_) => Vis::Public,
+        Visibility::Inherited => Vis::Inherited,
+        _ => Vis::Restricted(format_tokens(vis)),
+    }
+}
+
+fn format_generics(generics: &Generics) -> String {
+    format_tokens(generics)
+}
+
+// A function signature wrapped to the output width, given the columns already in use
+fn format_sig(sig: &Signature, indent: usize) -> String {
+    signature::wrapped(sig, indent, max_width())
+}
+
+fn format_tokens<T: ToTokens>(tokens: &T) -> String {
+    signature::flat(tokens)
+}
+
+fn with_where(head: String, preds: &[String]) -> String {
+    signature::with_where_clause(head, preds, max_width())
+}
+
+// Open the body of a declaration: on its own line if the declaration head is broken
+fn open_brace(decl: String) -> String {
+    if decl.contains('\n') {
+        format!("{}\n{{", decl)
+    } else {
+        format!("{} {{", decl)
+    }
+}
+
+// Each line indented one level, with a trailing newline
+fn indented(s: &str) -> String {
+    s.lines().map(|l| format!("{}{}\n", INDENT, l)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    const SOURCE: &str = r#"
+/// A point
+pub struct Point<T> where T: Copy { pub x: T, y: T }
+
+pub struct Wrapper(pub u8);
+
+pub struct Opaque { inner: u8 }
+
+pub struct Handle(pub u8, usize);
+
+pub enum Shape { Circle(f64), Square { side: f64 }, Empty = 3 }
+
+pub trait Area: Sized { const SIDES: usize; fn area(&self) -> f64; fn double(&self) -> f64 { 2.0 } }
+
+impl<T: Copy> Point<T> { pub fn new(x: T, y: T) -> Self { Point { x, y } } }
+
+impl Area for Shape { const SIDES: usize = 0; fn area(&self) -> f64 { 0.0 } }
+
+pub(crate) const LIMIT: usize = 10;
+static mut COUNT: u32 = 0;
+pub type Res<T> = Result<T, ()>;
+
+#[macro_export]
+macro_rules! shout { ($e:expr) => { $e }; ($($x:tt),* $(,)?) => {}; () => {}; }
+"#;
+
+    #[test_case("Point", "pub struct Point<T> where T: Copy {"; "named struct")]
+    #[test_case("Wrapper", "pub struct Wrapper(pub u8);"; "tuple struct")]
+    #[test_case("Point", "    /* private fields */"; "private fields")]
+    #[test_case("Opaque", "pub struct Opaque { /* private fields */ }"; "only private fields")]
+    #[test_case("Handle", "pub struct Handle(pub u8, /* private field */);"; "private tuple field")]
+    #[test_case("Shape", "    Square { side: f64 },"; "enum variants")]
+    #[test_case("Area", "    fn double(&self) -> f64 { ... }"; "trait items")]
+    #[test_case("LIMIT", "pub(crate) const LIMIT: usize = 10;"; "const item")]
+    #[test_case("COUNT", "static mut COUNT: u32 = 0;"; "static item")]
+    #[test_case("Res", "pub type Res<T> = Result<T, ()>;"; "type alias")]
+    #[test_case("shout", "    ($e:expr) => { ... };"; "macro arms")]
+    #[test_case("shout", "    ($($x:tt),* $(,)?) => { ... };"; "macro repetitions")]
+    fn items_are_rendered(name: &str, expected_line: &str) {
+        let items = extract_items("test", SOURCE).unwrap();
+        let rendered = items.render_item(name).unwrap();
+
+        assert!(rendered.lines().any(|l| l == expected_line), "{}", rendered);
+    }
+
+    #[test]
+    fn types_include_methods_and_trait_impls() {
+        let items = extract_items("test", SOURCE).unwrap();
+
+        let point = items.render_item("Point").unwrap();
+        assert!(point.contains("[Methods]\npub fn new(x: T, y: T) -> Self"));
+
+        let shape = items.render_item("Shape").unwrap();
+        assert!(shape.contains("[Trait Implementations]\nimpl Area for Shape"));
+    }
+
+    #[test]
+    fn methods_are_found_on_impls_and_traits() {
+        let items = extract_items("test", SOURCE).unwrap();
+
+        assert_eq!(
+            items.render_method("Point", "new"),
+            Some("pub fn new(x: T, y: T) -> Self".to_string())
+        );
+        assert_eq!(
+            items.render_method("Area", "area"),
+            Some("fn area(&self) -> f64".to_string())
+        );
+    }
+
+    #[test_case("krate::a", "a.rs"; "flat module file")]
+    #[test_case("krate::a::b", "a/b.rs"; "nested module file")]
+    #[test_case("krate::c", "renamed.rs"; "path attribute")]
+    #[test_case("krate::d", "d/mod.rs"; "mod rs file")]
+    #[test_case("krate::e", "lib.rs"; "inline module")]
+    #[test_case("krate::e::f", "e/f.rs"; "file module inside inline module")]
+    fn crate_modules_are_followed(path: &str, file: &str) {
+        let root = Path::new("test_resources/extract/krate");
+        let krate = extract_crate("krate", &root.join("lib.rs")).unwrap();
+
+        assert!(krate.module(path).is_some());
+        assert_eq!(krate.file(path), Some(root.join(file).as_path()));
+    }
+
+    #[test]
+    fn impls_are_stitched_onto_types_across_files() {
+        let krate =
+            extract_crate("krate", Path::new("test_resources/extract/krate/lib.rs")).unwrap();
+        let rendered = krate
+            .module("krate::a::b")
+            .unwrap()
+            .render_item("Point")
+            .unwrap();
+
+        assert!(
+            rendered.contains("[Methods]\npub fn origin() -> Self"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn item_paths_cover_the_module_tree() {
+        let krate =
+            extract_crate("krate", Path::new("test_resources/extract/krate/lib.rs")).unwrap();
+        let paths = krate.item_paths();
+
+        for p in &[
+            "krate::a::b::Point",
+            "krate::c::FROM_PATH",
+            "krate::d::in_mod_rs",
+            "krate::e::Inline",
+            "krate::e::f::Nested",
+        ] {
+            assert!(
+                paths.contains(&p.to_string()),
+                "{} missing from {:?}",
+                p,
+                paths
+            );
+        }
+    }
+
+    #[test]
+    fn public_api_follows_reexports() {
+        let krate = extract_crate("api", Path::new("test_resources/extract/api/lib.rs")).unwrap();
+        let api = krate.public_api();
+        let expected = vec![
+            ("api::HashMap", "std::collections::HashMap"),
+            ("api::Reexported", "api::private::Reexported"),
+            ("api::exported", "api::public::inner::exported"),
+            ("api::from_glob", "api::globbed::from_glob"),
+            ("api::public", "api::public"),
+            ("api::public::Renamed", "api::private::Reexported"),
+            ("api::public::visible", "api::public::visible"),
+        ];
+
+        assert_eq!(
+            api.iter()
+                .map(|(k, v)| (k.as_str(), v.as_str()))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert!(!api.contains_key("api::crate_only"));
+    }
+
+    #[test]
+    fn reexported_paths_resolve_to_their_definition() {
+        let krate = extract_crate("api", Path::new("test_resources/extract/api/lib.rs")).unwrap();
+        let components: Vec<String> = vec!["api".into(), "public".into(), "Renamed".into()];
+
+        assert_eq!(
+            krate.resolve(&components),
+            Some(("api::private".to_string(), vec!["Reexported".to_string()]))
+        );
+    }
+
+    #[test]
+    fn doc_attributes_are_interpreted() {
+        let krate = extract_crate("docs", Path::new("test_resources/extract/docs/lib.rs")).unwrap();
+        let items = krate.module("docs").unwrap();
+
+        assert!(items
+            .render_all()
+            .starts_with("[docs]\n# Docs crate\n\nIncluded from a file."));
+        assert_eq!(
+            items.render_item("Length").unwrap(),
+            "Visible docs\nOnly on docs.rs\npub struct Length(/* private fields */);\n\n[Methods]\npub fn get(&self) -> usize\n\n[Trait Implementations]\nimpl Measure for Length"
+        );
+        assert_eq!(items.render_item("surface"), items.render_item("measure"));
+        assert!(!items.render_all().contains("internal"));
+        assert!(!items.render_all().contains("OnlyHiddenOnDocsRs"));
+        assert!(!items.render_all().contains("INTERNAL_LIMIT"));
+        assert!(!items.render_all().contains("Internal"));
+        assert!(items.render_all().contains("pub fn get(&self) -> usize"));
+        for private in &["CrateOnly", "private_helper", "private_fn"] {
+            assert!(!items.render_all().contains(private), "{}", private);
+        }
+        assert_eq!(
+            krate.aliases(),
+            vec![
+                ("len".to_string(), "docs::Length".to_string()),
+                ("size".to_string(), "docs::Length".to_string()),
+                ("area".to_string(), "docs::measure".to_string()),
+                ("surface".to_string(), "docs::measure".to_string()),
+            ]
+        );
+    }
+
+    #[test_case(&["krate"], "lib.rs", 1, 1, 1..=18; "crate root")]
+    #[test_case(&["krate", "a", "b"], "a/b.rs", 1, 1, 1..=5; "file module")]
+    #[test_case(&["krate", "e"], "lib.rs", 7, 9, 7..=12; "inline module")]
+    #[test_case(&["krate", "a", "b", "Point"], "a/b.rs", 2, 12, 2..=5; "struct item")]
+    #[test_case(&["krate", "a", "b", "Point", "origin"], "lib.rs", 15, 12, 15..=17; "method in another file")]
+    fn items_are_located(
+        query: &[&str],
+        file: &str,
+        line: usize,
+        column: usize,
+        lines: RangeInclusive<usize>,
+    ) {
+        let root = Path::new("test_resources/extract/krate");
+        let krate = extract_crate("krate", &root.join("lib.rs")).unwrap();
+        let components: Vec<String> = query.iter().map(|s| s.to_string()).collect();
+
+        assert_eq!(
+            krate.location(&components),
+            Some(Location {
+                file: root.join(file),
+                line,
+                column,
+                lines,
+            })
+        );
+    }
 }
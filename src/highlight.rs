@@ -0,0 +1,286 @@
+/*!
+ * Minimal syntax highlighting for Rust source code shown in the terminal
+ *
+ * This is a simple lexer rather than a full parser: it only needs to be good enough to
+ * pick out keywords, literals and comments so that the structure of the code stands out.
+ */
+use colored::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Plain,
+    Keyword,
+    Type,
+    Macro,
+    Lifetime,
+    Number,
+    Str,
+    Comment,
+}
+
+impl Kind {
+    fn paint(self, s: &str) -> String {
+        match self {
+            Kind::Plain => s.to_string(),
+            Kind::Keyword => s.magenta().to_string(),
+            Kind::Type => s.cyan().to_string(),
+            Kind::Macro => s.blue().to_string(),
+            Kind::Lifetime | Kind::Number => s.yellow().to_string(),
+            Kind::Str => s.green().to_string(),
+            Kind::Comment => s.dimmed().to_string(),
+        }
+    }
+}
+
+/// Color the given Rust source code for output to the terminal
+pub(crate) fn highlight(code: &str) -> String {
+    // Color each line separately so that escape codes never span a line break
+    tokens(code)
+        .into_iter()
+        .map(|(kind, s)| {
+            s.split('\n')
+                .map(|line| {
+                    if line.is_empty() {
+                        String::new()
+                    } else {
+                        kind.paint(line)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect()
+}
+
+// Split source code into tokens, keeping all whitespace so that joining the tokens back
+// together gives the original code
+fn tokens(code: &str) -> Vec<(Kind, &str)> {
+    let chars: Vec<(usize, char)> = code.char_indices().collect();
+    let offset = |i: usize| chars.get(i).map_or(code.len(), |&(o, _)| o);
+    let at = |i: usize| chars.get(i).map(|&(_, c)| c);
+
+    let mut toks: Vec<(Kind, &str)> = vec![];
+    let mut i = 0;
+
+    while let Some(c) = at(i) {
+        let start = i;
+        let kind = match c {
+            '/' if at(i + 1) == Some('/') => {
+                while at(i).is_some_and(|c| c != '\n') {
+                    i += 1;
+                }
+                Kind::Comment
+            }
+            '/' if at(i + 1) == Some('*') => {
+                let mut depth = 0;
+                while let Some(c) = at(i) {
+                    if c == '/' && at(i + 1) == Some('*') {
+                        depth += 1;
+                        i += 1;
+                    } else if c == '*' && at(i + 1) == Some('/') {
+                        depth -= 1;
+                        i += 1;
+                    }
+                    i += 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                Kind::Comment
+            }
+            '"' => {
+                i = end_of_string(&at, i + 1);
+                Kind::Str
+            }
+            'r' | 'b' if raw_string_hashes(&at, i).is_some() => {
+                let (hashes, open) = raw_string_hashes(&at, i).unwrap();
+                i = open + 1;
+                while let Some(c) = at(i) {
+                    i += 1;
+                    if c == '"' && (0..hashes).all(|n| at(i + n) == Some('#')) {
+                        i += hashes;
+                        break;
+                    }
+                }
+                Kind::Str
+            }
+            'b' if at(i + 1) == Some('"') => {
+                i = end_of_string(&at, i + 2);
+                Kind::Str
+            }
+            '\'' | 'b' if char_literal_len(&at, if c == 'b' { i + 1 } else { i }).is_some() => {
+                let from = if c == 'b' { i + 1 } else { i };
+                i = from + char_literal_len(&at, from).unwrap();
+                Kind::Str
+            }
+            '\'' if at(i + 1).is_some_and(is_ident_start) => {
+                i += 1;
+                while at(i).is_some_and(is_ident_char) {
+                    i += 1;
+                }
+                Kind::Lifetime
+            }
+            c if c.is_ascii_digit() => {
+                while at(i).is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                    // Don't swallow a range or a method call on an integer literal
+                    if at(i) == Some('.') && !at(i + 1).is_some_and(|c| c.is_ascii_digit()) {
+                        break;
+                    }
+                    i += 1;
+                }
+                Kind::Number
+            }
+            c if is_ident_start(c) => {
+                while at(i).is_some_and(is_ident_char) {
+                    i += 1;
+                }
+                let word = &code[offset(start)..offset(i)];
+                if is_keyword(word) {
+                    Kind::Keyword
+                } else if at(i) == Some('!') && at(i + 1) != Some('=') {
+                    i += 1;
+                    Kind::Macro
+                } else if word.starts_with(|c: char| c.is_uppercase()) {
+                    Kind::Type
+                } else {
+                    Kind::Plain
+                }
+            }
+            _ => {
+                i += 1;
+                Kind::Plain
+            }
+        };
+
+        let s = &code[offset(start)..offset(i)];
+        match toks.last_mut() {
+            Some((Kind::Plain, prev)) if kind == Kind::Plain => {
+                let from = offset(start) - prev.len();
+                *prev = &code[from..offset(i)];
+            }
+            _ => toks.push((kind, s)),
+        }
+    }
+
+    toks
+}
+
+// The index just past the closing quote of a string whose contents start at i
+fn end_of_string(at: &impl Fn(usize) -> Option<char>, mut i: usize) -> usize {
+    while let Some(c) = at(i) {
+        i += 1;
+        match c {
+            '\\' => i += 1,
+            '"' => break,
+            _ => (),
+        }
+    }
+    i
+}
+
+// The number of hashes and the index of the opening quote if a raw string starts at i
+fn raw_string_hashes(at: &impl Fn(usize) -> Option<char>, i: usize) -> Option<(usize, usize)> {
+    let mut j = i;
+    if at(j) == Some('b') {
+        j += 1;
+    }
+    if at(j) != Some('r') {
+        return None;
+    }
+    j += 1;
+    let hashes = (j..).take_while(|&k| at(k) == Some('#')).count();
+
+    if at(j + hashes) == Some('"') {
+        Some((hashes, j + hashes))
+    } else {
+        None
+    }
+}
+
+// The length of a char literal starting at i, if there is one there rather than a lifetime
+fn char_literal_len(at: &impl Fn(usize) -> Option<char>, i: usize) -> Option<usize> {
+    if at(i) != Some('\'') {
+        return None;
+    }
+    match at(i + 1)? {
+        '\\' => {
+            let close = (i + 2..i + 12).find(|&j| at(j) == Some('\''))?;
+            Some(close - i + 1)
+        }
+        _ if at(i + 2) == Some('\'') => Some(3),
+        _ => None,
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn is_keyword(w: &str) -> bool {
+    matches!(
+        w,
+        "as" | "async"
+            | "await"
+            | "break"
+            | "const"
+            | "continue"
+            | "crate"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "self"
+            | "Self"
+            | "static"
+            | "struct"
+            | "super"
+            | "trait"
+            | "true"
+            | "type"
+            | "union"
+            | "unsafe"
+            | "use"
+            | "where"
+            | "while"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("pub fn foo() {}", &[(Kind::Keyword, "pub"), (Kind::Plain, " "), (Kind::Keyword, "fn"), (Kind::Plain, " foo() {}")]; "keywords")]
+    #[test_case("x: Vec<u8>", &[(Kind::Plain, "x: "), (Kind::Type, "Vec"), (Kind::Plain, "<u8>")]; "types")]
+    #[test_case("println!(\"a \\\" b\")", &[(Kind::Macro, "println!"), (Kind::Plain, "("), (Kind::Str, "\"a \\\" b\""), (Kind::Plain, ")")]; "macro and string")]
+    #[test_case("&'a str", &[(Kind::Plain, "&"), (Kind::Lifetime, "'a"), (Kind::Plain, " str")]; "lifetime")]
+    #[test_case("'a' '\\n'", &[(Kind::Str, "'a'"), (Kind::Plain, " "), (Kind::Str, "'\\n'")]; "chars")]
+    #[test_case("r#\"raw \" str\"#", &[(Kind::Str, "r#\"raw \" str\"#")]; "raw string")]
+    #[test_case("0..10.0f64", &[(Kind::Number, "0"), (Kind::Plain, ".."), (Kind::Number, "10.0f64")]; "numbers")]
+    #[test_case("a // b\n/* c /* d */ */", &[(Kind::Plain, "a "), (Kind::Comment, "// b"), (Kind::Plain, "\n"), (Kind::Comment, "/* c /* d */ */")]; "comments")]
+    fn code_is_tokenized(code: &str, expected: &[(Kind, &str)]) {
+        let toks = tokens(code);
+
+        assert_eq!(toks, expected);
+        assert_eq!(toks.into_iter().map(|(_, s)| s).collect::<String>(), code);
+    }
+}
@@ -5,7 +5,17 @@
 //! entirely on the local HTML output created by running `cargo doc` in the root of
 //! your crate. You will need to have rust installed via rustup and have the std lib
 //! docs downloaded in order to look at std lib.
+pub mod complete;
+pub mod cursor;
+pub mod diff;
+pub mod explain;
 pub mod extract;
-mod locate;
-mod parse;
+mod highlight;
+pub mod locate;
+pub mod lockfile;
+pub mod parse;
 mod pprint;
+pub mod server;
+mod signature;
+pub mod source;
+pub mod tui;
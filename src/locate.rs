@@ -1,30 +1,373 @@
 /*!
  * Locate the generated docs that we have available within the current workspace
  */
-use crate::pprint::{header, pprint_as_columns, CRATE_LIST_HEADING_COLOR};
+use crate::{
+    extract::Location,
+    lockfile::{Lockfile, Origin, Package},
+    parse::{crate_summary, rendered_source_code},
+    pprint::{header, pprint_as_columns, Table, CRATE_LIST_HEADING_COLOR},
+};
+use std::collections::HashSet;
 use std::fs;
-use std::{env, ffi, path, process};
+use std::ops::RangeInclusive;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, ffi, fmt, path, process};
 
-/// Determine the known crates under this path
+/// Environment variable used to select the toolchain whose std docs we search
+pub const TOOLCHAIN_ENV_VAR: &str = "ROC_TOOLCHAIN";
+
+// Where we cache the sysroot of the default toolchain between runs
+const SYSROOT_CACHE_FILE: &str = "roc/sysroots";
+
+// The number of directories whose default sysroot we remember
+const SYSROOT_CACHE_ENTRIES: usize = 64;
+
+// Files that override the default toolchain for the directory they are in (and below)
+const TOOLCHAIN_FILES: &[&str] = &["rust-toolchain", "rust-toolchain.toml"];
+
+// The sysroot of the toolchain selected with set_toolchain
+static SELECTED_SYS_ROOT: OnceLock<path::PathBuf> = OnceLock::new();
+
+// The path of the std docs within a toolchain sysroot
+const STD_DOCS_PATH: &str = "share/doc/rust/html";
+
+// The path of the std library sources (the rust-src component) within a toolchain sysroot
+const STD_SOURCE_PATH: &str = "lib/rustlib/src/rust/library";
+
+// Crates whose sources ship with the rust-src component
+const STD_CRATES: &[&str] = &["alloc", "core", "proc_macro", "std", "test"];
+
+/// Primitive types documented by std that can be queried without a module path
+const PRIMITIVES: &[&str] = &[
+    "array",
+    "bool",
+    "char",
+    "f32",
+    "f64",
+    "fn",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "isize",
+    "never",
+    "pointer",
+    "reference",
+    "slice",
+    "str",
+    "tuple",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "unit",
+    "usize",
+];
+
+/**
+ * Determine the known crates under this path. Where we can find a Cargo.lock we
+ * also show the locked version of each crate and how it relates to the workspace,
+ * flagging doc directories that are no longer referenced by the lockfile.
+ */
 pub fn list_known_crates() -> std::io::Result<()> {
-    let mut dirs = vec![String::from("std")];
+    let mut rows: Vec<Vec<String>> = vec![];
+    let lockfile = Lockfile::find();
+
+    if let Some(root) = get_doc_root(&CrateType::StdLib) {
+        let summary = crate_summary(&root.join("std/index.html")).unwrap_or_default();
+        rows.push(vec!["std".into(), String::new(), "std".into(), summary]);
+    }
 
     if let Some(root) = get_doc_root(&CrateType::Cargo) {
-        for res in root.read_dir()? {
-            let entry = res?;
-            let meta = entry.metadata()?;
-            if meta.is_dir() && entry.file_name() != "src" {
-                dirs.push(String::from(entry.file_name().to_str().unwrap()));
-            }
+        for dir in known_crate_dirs(&root)? {
+            let summary = crate_summary(&root.join(&dir).join("index.html")).unwrap_or_default();
+            let (version, origin) = match &lockfile {
+                Some(lock) => (
+                    lock.package(&dir)
+                        .map(|p| p.version.clone())
+                        .unwrap_or_default(),
+                    match lock.origin(&dir) {
+                        Some(Origin::Workspace) => "workspace",
+                        Some(Origin::Path) => "path",
+                        Some(Origin::Direct) => "direct",
+                        Some(Origin::Transitive) => "transitive",
+                        None => "stale",
+                    },
+                ),
+                None => (String::new(), ""),
+            };
+            rows.push(vec![dir, version, origin.into(), summary]);
         }
     }
 
-    dirs.sort();
+    rows.sort();
     let title = header("known crates", CRATE_LIST_HEADING_COLOR);
-    println!("{}\n{}", title, pprint_as_columns(dirs));
+    println!("{}\n{}", title, Table::from_rows(rows).as_string());
+    Ok(())
+}
+
+/**
+ * Print the dependency tree of the current workspace from its Cargo.lock in the
+ * style of `cargo tree`, along with the summary of each crate from its docs.
+ * Crates that have been seen before are marked with (*) and not expanded again.
+ */
+pub fn show_dependency_tree() -> std::io::Result<()> {
+    let lockfile = Lockfile::find().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::NotFound, "unable to locate Cargo.lock")
+    })?;
+    let rows = DependencyTree::new(&lockfile, get_doc_root(&CrateType::Cargo)).rows();
+
+    let title = header("dependencies", CRATE_LIST_HEADING_COLOR);
+    println!("{}\n{}", title, Table::from_rows(rows).as_string());
+    Ok(())
+}
+
+// The rows of the dependency tree of a workspace along with the summary of each crate
+struct DependencyTree<'a> {
+    lockfile: &'a Lockfile,
+    doc_root: Option<path::PathBuf>,
+    seen: HashSet<(String, String)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl<'a> DependencyTree<'a> {
+    fn new(lockfile: &'a Lockfile, doc_root: Option<path::PathBuf>) -> Self {
+        DependencyTree {
+            lockfile,
+            doc_root,
+            seen: HashSet::new(),
+            rows: vec![],
+        }
+    }
+
+    fn rows(mut self) -> Vec<Vec<String>> {
+        for member in self.lockfile.workspace_members() {
+            self.add(member, "", "");
+        }
+
+        self.rows
+    }
+
+    fn add(&mut self, pkg: &Package, prefix: &str, branch: &str) {
+        let repeated = !self.seen.insert((pkg.name.clone(), pkg.version.clone()));
+        let marker = if repeated && !pkg.dependencies.is_empty() {
+            " (*)"
+        } else {
+            ""
+        };
+
+        let index = self
+            .doc_root
+            .as_ref()
+            .map(|r| r.join(pkg.doc_dir_name()).join("index.html"));
+        let summary = match index {
+            Some(index) if index.is_file() => crate_summary(&index).unwrap_or_default(),
+            _ => String::from("(no docs)"),
+        };
+
+        self.rows.push(vec![
+            format!(
+                "{}{}{} v{}{}",
+                prefix, branch, pkg.name, pkg.version, marker
+            ),
+            summary,
+        ]);
+        if repeated {
+            return;
+        }
+
+        let child_prefix = match branch {
+            "|-- " => format!("{}|   ", prefix),
+            "`-- " => format!("{}    ", prefix),
+            _ => prefix.to_string(),
+        };
+        let lockfile = self.lockfile;
+        let deps: Vec<&Package> = pkg
+            .dependencies
+            .iter()
+            .filter_map(|(name, version)| lockfile.dependency(name, version))
+            .collect();
+
+        for (i, dep) in deps.iter().enumerate() {
+            let branch = if i == deps.len() - 1 { "`-- " } else { "|-- " };
+            self.add(dep, &child_prefix, branch);
+        }
+    }
+}
+
+/// The names of the crate directories under a rustdoc output directory
+pub fn known_crate_dirs(root: &path::Path) -> std::io::Result<Vec<String>> {
+    let mut dirs = vec![];
+    for res in root.read_dir()? {
+        let entry = res?;
+        let meta = entry.metadata()?;
+        if meta.is_dir() && entry.file_name() != "src" && entry.path().join("index.html").is_file()
+        {
+            dirs.push(String::from(entry.file_name().to_str().unwrap()));
+        }
+    }
+
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// List the installed rustup toolchains that have the rust-docs component
+pub fn list_toolchains() -> std::io::Result<()> {
+    let dir = get_rustup_home()
+        .map(|h| h.join("toolchains"))
+        .ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "unable to locate rustup")
+        })?;
+
+    let mut toolchains = vec![];
+    for res in dir.read_dir()? {
+        let entry = res?;
+        if entry
+            .path()
+            .join(STD_DOCS_PATH)
+            .join("std/index.html")
+            .is_file()
+        {
+            toolchains.push(String::from(entry.file_name().to_str().unwrap()));
+        }
+    }
+
+    if toolchains.is_empty() {
+        println!("No toolchains with the rust-docs component found");
+        return Ok(());
+    }
+
+    toolchains.sort();
+    let title = header("toolchains with docs", CRATE_LIST_HEADING_COLOR);
+    println!("{}\n{}", title, pprint_as_columns(toolchains));
+    Ok(())
+}
+
+/**
+ * Select the toolchain whose std docs (and rustc) are used for the remainder of this
+ * process. Fails if there is no installed toolchain with the given name.
+ */
+pub fn set_toolchain(name: &str) -> std::io::Result<()> {
+    let root = toolchain_sys_root(name).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("unknown toolchain: {}", name),
+        )
+    })?;
+
+    let _ = SELECTED_SYS_ROOT.set(root);
+    Ok(())
+}
+
+/**
+ * The sysroot of an installed rustup toolchain. Names can be given in full or
+ * without their host triple (stable, nightly-2021-01-01, 1.70) in which case
+ * the first matching installed toolchain is used.
+ */
+pub fn toolchain_sys_root(name: &str) -> Option<path::PathBuf> {
+    let dir = get_rustup_home()?.join("toolchains");
+    let exact = dir.join(name);
+    if exact.is_dir() {
+        return Some(exact);
+    }
+
+    let mut candidates: Vec<path::PathBuf> = dir
+        .read_dir()
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_name().to_str().is_some_and(|f| {
+                f.starts_with(&format!("{}-", name)) || f.starts_with(&format!("{}.", name))
+            })
+        })
+        .map(|e| e.path())
+        .collect();
+
+    candidates.sort();
+    candidates.into_iter().next()
+}
+
+/// The doc root containing the std docs of an installed rustup toolchain
+pub fn toolchain_doc_root(name: &str) -> Option<path::PathBuf> {
+    toolchain_sys_root(name).map(|r| r.join(STD_DOCS_PATH))
+}
+
+/// Every documentation page under the given module of a doc root, tagged with its kind
+pub fn walk_tagged_paths(
+    root: &path::Path,
+    module_path: &[String],
+) -> std::io::Result<Vec<TaggedPath>> {
+    let mut found = vec![];
+    let start = module_path
+        .iter()
+        .fold(root.to_path_buf(), |p, c| p.join(c));
+    walk_dir(root, &start, &mut found)?;
+    found.sort_by(|a, b| a.path_buf.cmp(&b.path_buf));
+
+    Ok(found)
+}
+
+fn walk_dir(
+    root: &path::Path,
+    dir: &path::Path,
+    found: &mut Vec<TaggedPath>,
+) -> std::io::Result<()> {
+    for res in dir.read_dir()? {
+        let path = res?.path();
+        if path.is_dir() {
+            if path.file_name().is_some_and(|f| f != "src") {
+                walk_dir(root, &path, found)?;
+            }
+        } else if path.extension().is_some_and(|e| e == "html") {
+            let mut tagged = TaggedPath::from(path);
+            if tagged.tag != Tag::Unknown {
+                tagged.root = Some(root.to_path_buf());
+                found.push(tagged);
+            }
+        }
+    }
+
     Ok(())
 }
 
+/// The TaggedPath for the std index page, which lists every documented keyword
+pub fn std_index_tagged_path() -> Option<TaggedPath> {
+    std_tagged_path("index.html")
+}
+
+/// The TaggedPath for the index page of the crate in the current directory (if it is documented)
+pub fn crate_index_tagged_path() -> Option<TaggedPath> {
+    let crate_root = get_crate_root()?;
+    let name = manifest_package_name(&crate_root.join("Cargo.toml"))?.replace('-', "_");
+    let root = crate_root.join("target/doc");
+    let path_buf = root.join(name).join("index.html");
+    if !path_buf.is_file() {
+        return None;
+    }
+
+    Some(TaggedPath::with_root(path_buf, &root))
+}
+
+/// The TaggedPath for the std documentation page of a language keyword
+pub fn keyword_tagged_path(keyword: &str) -> Option<TaggedPath> {
+    std_tagged_path(&format!("keyword.{}.html", keyword))
+}
+
+fn std_tagged_path(file_name: &str) -> Option<TaggedPath> {
+    let root = get_doc_root(&CrateType::StdLib)?;
+    let path_buf = root.join("std").join(file_name);
+    if !path_buf.is_file() {
+        return None;
+    }
+
+    let mut tagged = TaggedPath::from(path_buf);
+    tagged.root = Some(root);
+    Some(tagged)
+}
+
 /// Each of the various documentation types we can be asked to locate
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Tag {
@@ -34,22 +377,52 @@ pub enum Tag {
     Enum,
     /// A top level function
     Function,
-    /// An exported macro
+    /// An exported macro: macro_rules!, derive or attribute proc-macros
     Macro,
+    /// A std documentation page for a language keyword
+    Keyword,
     /// A child module under the current search path
     Module,
-    /// A stdlib primative data type
-    Primative,
+    /// A public static item
+    Static,
+    /// A stdlib primitive data type
+    Primitive,
     /// A struct (we wont see hidden fields)
     Struct,
     /// A trait definition
     Trait,
     /// Methods on a struct if that's what the current path points to
     Method,
+    /// A variant of an enum (documented on the page of the enum itself)
+    Variant,
+    /// A trait implementation for a type (documented on the page of the type)
+    TraitImpl,
     /// Something we don't know how to handle yet
     Unknown,
 }
 
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Tag::Constant => "constant",
+            Tag::Enum => "enum",
+            Tag::Function => "fn",
+            Tag::Keyword => "keyword",
+            Tag::Macro => "macro",
+            Tag::Module => "mod",
+            Tag::Static => "static",
+            Tag::Primitive => "primitive",
+            Tag::Struct => "struct",
+            Tag::Trait => "trait",
+            Tag::Method => "method",
+            Tag::Variant => "variant",
+            Tag::TraitImpl => "impl",
+            Tag::Unknown => "unknown",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 impl From<path::PathBuf> for Tag {
     fn from(path_buf: path::PathBuf) -> Tag {
         let file_name = path_buf.file_name().unwrap();
@@ -63,8 +436,10 @@ impl From<path::PathBuf> for Tag {
                 "constant" => Tag::Constant,
                 "enum" => Tag::Enum,
                 "fn" => Tag::Function,
-                "macro" => Tag::Macro,
-                "primative" => Tag::Primative,
+                "keyword" => Tag::Keyword,
+                "macro" | "derive" | "attr" => Tag::Macro,
+                "primitive" => Tag::Primitive,
+                "static" => Tag::Static,
                 "struct" => Tag::Struct,
                 "trait" => Tag::Trait,
                 _ => Tag::Unknown,
@@ -77,6 +452,7 @@ impl From<path::PathBuf> for Tag {
 #[derive(PartialEq, Eq, Debug, Clone)]
 pub struct TaggedPath {
     path_buf: path::PathBuf,
+    root: Option<path::PathBuf>,
     without_prefix: Option<ffi::OsString>,
     /// The file name that was located
     pub file_name: String,
@@ -92,7 +468,53 @@ impl TaggedPath {
         String::from(self.path_buf.to_str().unwrap())
     }
 
-    fn dir(&self) -> String {
+    /// The name of the documented item this path points at (without its type prefix)
+    pub fn item_name(&self) -> Option<String> {
+        self.without_prefix
+            .as_ref()
+            .and_then(|p| p.to_str())
+            .map(|s| s.trim_end_matches(".html").to_string())
+    }
+
+    /// The documentation root that this path was located under (if known)
+    pub fn root(&self) -> Option<path::PathBuf> {
+        self.root.clone()
+    }
+
+    // A tagged path for a page that is known to be under the given documentation root
+    pub(crate) fn with_root(path_buf: path::PathBuf, root: &path::Path) -> Self {
+        let mut tagged = TaggedPath::from(path_buf);
+        tagged.root = Some(root.to_path_buf());
+        tagged
+    }
+
+    /// The module path components from the documentation root down to the containing
+    /// directory of this file, e.g. ["std", "collections", "hash_map"]
+    pub fn module_path(&self) -> Vec<String> {
+        let dir = path::PathBuf::from(self.dir());
+        let relative = match &self.root {
+            Some(root) => dir
+                .strip_prefix(root)
+                .map(|p| p.to_path_buf())
+                .unwrap_or(dir),
+            None => return vec![],
+        };
+
+        relative
+            .components()
+            .filter_map(|c| c.as_os_str().to_str())
+            .map(String::from)
+            .collect()
+    }
+
+    /// The full item path of the documented item, e.g. std::collections::HashMap
+    pub fn item_path(&self) -> String {
+        let mut components = self.module_path();
+        components.extend(self.item_name());
+        components.join("::")
+    }
+
+    pub(crate) fn dir(&self) -> String {
         let mut dir = self.path_buf.clone();
         dir.pop();
         String::from(dir.to_str().unwrap())
@@ -130,6 +552,7 @@ impl From<path::PathBuf> for TaggedPath {
 
         TaggedPath {
             path_buf,
+            root: None,
             file_name,
             without_prefix,
             method_name,
@@ -153,6 +576,7 @@ enum CrateType {
 #[derive(PartialEq, Eq, Debug)]
 enum QueryType {
     InstanceMethod,
+    Primitive,
     Unknown,
 }
 
@@ -168,36 +592,52 @@ pub struct Locator {
     components: Vec<String>,
 }
 
+// Is the first component of a query a primitive type? Local crates shadow primitives with
+// the same name (array, unit, pointer...) so that they can still be queried.
+fn is_primitive(first: &str, cargo_root: Option<&path::Path>) -> bool {
+    PRIMITIVES.contains(&first) && !cargo_root.is_some_and(|r| r.join(first).is_dir())
+}
+
 impl Locator {
     /// Create a new Locator based on the given user query path entered at the command line
     pub fn new(query: String) -> Self {
-        let components: Vec<String> = query
+        Locator::try_new(query).expect("unable to locate documentation root")
+    }
+
+    /// Create a new Locator, returning None if the query is empty or if there is no
+    /// documentation root to search for it in
+    pub fn try_new(query: String) -> Option<Self> {
+        let mut components: Vec<String> = query
             .split("::")
             .flat_map(|s| s.split('.'))
             .filter(|s| !s.is_empty())
             .map(String::from)
             .collect();
 
-        let crate_type = if components[0] == "std" {
-            CrateType::StdLib
+        let cargo_root = get_doc_root(&CrateType::Cargo);
+        let query_type = if is_primitive(components.first()?, cargo_root.as_deref()) {
+            components.insert(0, String::from("std"));
+            QueryType::Primitive
+        } else if query.contains('.') {
+            QueryType::InstanceMethod
         } else {
-            CrateType::Cargo
+            QueryType::Unknown
         };
 
-        let query_type = if query.contains('.') {
-            QueryType::InstanceMethod
+        let crate_type = if components[0] == "std" {
+            CrateType::StdLib
         } else {
-            QueryType::Unknown
+            CrateType::Cargo
         };
 
-        let root = get_doc_root(&crate_type).expect("unable to locate documentation root");
+        let root = get_doc_root(&crate_type)?;
 
-        return Locator {
+        Some(Locator {
             root,
             crate_type,
             query_type,
             components,
-        };
+        })
     }
 
     /// The resolved local file path if we were able to determine one
@@ -207,6 +647,17 @@ impl Locator {
 
     /// The resolved local TaggedPath (path with added metadata)
     pub fn determine_tagged_path(&self) -> Option<TaggedPath> {
+        self.search_tagged_path().map(|mut tagged| {
+            tagged.root = Some(self.root.clone());
+            tagged
+        })
+    }
+
+    fn search_tagged_path(&self) -> Option<TaggedPath> {
+        if self.query_type == QueryType::Primitive {
+            return self.primitive_tagged_path();
+        }
+
         let mut search_path = self.root.clone();
         search_path.extend(self.query_dir_as_path_buf().iter());
 
@@ -256,6 +707,23 @@ impl Locator {
         return None;
     }
 
+    // Primitives live directly under the std root rather than in a module directory
+    fn primitive_tagged_path(&self) -> Option<TaggedPath> {
+        let file_name = format!("primitive.{}.html", self.components[1]);
+        let path_buf = self.root.join("std").join(file_name);
+        if !path_buf.is_file() {
+            return None;
+        }
+
+        let mut tagged = TaggedPath::from(path_buf);
+        if self.components.len() > 2 {
+            tagged.tag = Tag::Method;
+            tagged.method_name = Some(self.components[self.components.len() - 1].clone());
+        }
+
+        Some(tagged)
+    }
+
     fn query_dir_as_path_buf(&self) -> path::PathBuf {
         let mut buf = path::PathBuf::new();
         buf.extend(self.components.iter());
@@ -290,24 +758,454 @@ impl Locator {
     }
 }
 
+/// The documentation roots that exist locally: the std docs followed by target/doc
+pub fn doc_roots() -> Vec<path::PathBuf> {
+    [CrateType::StdLib, CrateType::Cargo]
+        .iter()
+        .filter_map(get_doc_root)
+        .filter(|root| root.is_dir())
+        .collect()
+}
+
 fn get_doc_root(crate_type: &CrateType) -> Option<path::PathBuf> {
     match crate_type {
-        CrateType::StdLib => get_sys_root().map(|r| r.join(path::Path::new("share/doc/rust/html"))),
+        CrateType::StdLib => get_sys_root().map(|r| r.join(path::Path::new(STD_DOCS_PATH))),
         CrateType::Cargo => get_crate_root().map(|r| r.join(path::Path::new("target/doc"))),
     }
 }
 
-fn get_sys_root() -> Option<path::PathBuf> {
-    process::Command::new("rustc")
-        .arg("--print")
-        .arg("sysroot")
-        .output()
-        .ok()
-        .and_then(|out| String::from_utf8(out.stdout).ok())
-        .map(|s| path::Path::new(s.trim()).to_path_buf())
+/// A rustc command for the selected toolchain
+pub(crate) fn rustc_command() -> process::Command {
+    match SELECTED_SYS_ROOT.get() {
+        Some(root) => process::Command::new(root.join("bin/rustc")),
+        None => process::Command::new("rustc"),
+    }
 }
 
-fn get_crate_root() -> Option<path::PathBuf> {
+/**
+ * The sysroot of the selected toolchain. If no toolchain has been selected then
+ * we ask the default rustc, caching the result on disk so that we only need to
+ * spawn rustc again when the rustup settings or a rust-toolchain file change.
+ */
+pub(crate) fn get_sys_root() -> Option<path::PathBuf> {
+    if let Some(root) = SELECTED_SYS_ROOT.get() {
+        return Some(root.clone());
+    }
+
+    static DEFAULT_SYS_ROOT: OnceLock<Option<path::PathBuf>> = OnceLock::new();
+    DEFAULT_SYS_ROOT
+        .get_or_init(|| {
+            let dir = sys_root_cache_dir();
+            let key = sys_root_cache_key(&dir);
+            if let Some(root) = read_cached_sys_root(&key, &dir) {
+                return Some(root);
+            }
+
+            let root = process::Command::new("rustc")
+                .arg("--print")
+                .arg("sysroot")
+                .output()
+                .ok()
+                .and_then(|out| String::from_utf8(out.stdout).ok())
+                .map(|s| path::Path::new(s.trim()).to_path_buf())?;
+
+            write_cached_sys_root(&key, &root);
+            Some(root)
+        })
+        .clone()
+}
+
+// The default toolchain can be overridden per directory so we key the cache on the
+// crate root (or the current directory when we're outside of a crate).
+fn sys_root_cache_dir() -> path::PathBuf {
+    get_crate_root()
+        .or_else(|| env::current_dir().ok())
+        .unwrap_or_default()
+}
+
+fn sys_root_cache_key(dir: &path::Path) -> String {
+    let rustup_toolchain = env::var("RUSTUP_TOOLCHAIN").unwrap_or_default();
+
+    format!("{}|{}", dir.display(), rustup_toolchain)
+}
+
+fn sys_root_cache_path() -> Option<path::PathBuf> {
+    let cache_dir = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => path::PathBuf::from(dir),
+        None => path::PathBuf::from(env::var_os("HOME")?).join(".cache"),
+    };
+
+    Some(cache_dir.join(SYSROOT_CACHE_FILE))
+}
+
+fn read_cached_sys_root(key: &str, dir: &path::Path) -> Option<path::PathBuf> {
+    let contents = fs::read_to_string(sys_root_cache_path()?).ok()?;
+
+    // Anything that may have changed which toolchain is the default invalidates an entry
+    let mut sources: Vec<path::PathBuf> = get_rustup_home()
+        .map(|h| h.join("settings.toml"))
+        .into_iter()
+        .collect();
+    sources.extend(toolchain_files(dir));
+
+    cached_sys_root(&contents, key, &sources)
+}
+
+fn write_cached_sys_root(key: &str, root: &path::Path) {
+    let cache = match sys_root_cache_path() {
+        Some(cache) => cache,
+        None => return,
+    };
+    let contents = fs::read_to_string(&cache).unwrap_or_default();
+    let updated = updated_sys_root_cache(&contents, key, root, unix_time(SystemTime::now()));
+
+    // Failing to cache isn't fatal: we'll just ask rustc again next time
+    if let Some(dir) = cache.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(&cache, updated);
+}
+
+// The rust-toolchain files that apply to a directory: rustup uses the closest one
+fn toolchain_files(dir: &path::Path) -> Vec<path::PathBuf> {
+    dir.ancestors()
+        .flat_map(|d| TOOLCHAIN_FILES.iter().map(move |f| d.join(f)))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+// Cache entries are lines of "key<tab>cached at<tab>sysroot". An entry is stale if any
+// of the given files has been modified since it was written.
+fn cached_sys_root(contents: &str, key: &str, sources: &[path::PathBuf]) -> Option<path::PathBuf> {
+    let (cached_at, root) = contents.lines().find_map(|line| {
+        let mut fields = line.splitn(3, '\t');
+        if fields.next()? != key {
+            return None;
+        }
+        let cached_at: u64 = fields.next()?.parse().ok()?;
+        Some((cached_at, path::PathBuf::from(fields.next()?)))
+    })?;
+
+    let stale = sources
+        .iter()
+        .filter_map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+        .any(|modified| unix_time(modified) >= cached_at);
+
+    if stale || !root.is_dir() {
+        None
+    } else {
+        Some(root)
+    }
+}
+
+// The cache contents with the entry for key replaced, dropping the oldest entries so that
+// the cache doesn't keep growing as we're run from new directories
+fn updated_sys_root_cache(contents: &str, key: &str, root: &path::Path, now: u64) -> String {
+    let mut lines: Vec<String> = contents
+        .lines()
+        .filter(|line| line.split_once('\t').is_some_and(|(k, _)| k != key))
+        .map(String::from)
+        .collect();
+    lines.push(format!("{}\t{}\t{}", key, now, root.display()));
+
+    let excess = lines.len().saturating_sub(SYSROOT_CACHE_ENTRIES);
+    lines.drain(..excess);
+    lines.join("\n") + "\n"
+}
+
+fn unix_time(t: SystemTime) -> u64 {
+    t.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn get_rustup_home() -> Option<path::PathBuf> {
+    match env::var_os("RUSTUP_HOME") {
+        Some(dir) => Some(path::PathBuf::from(dir)),
+        None => Some(path::PathBuf::from(env::var_os("HOME")?).join(".rustup")),
+    }
+}
+
+/**
+ * Resolve a rustdoc source page link (e.g. "../src/foo/lib.rs.html#10-20") found on the
+ * page of a tagged path to the rendered source page along with the lines it points at.
+ */
+pub fn rendered_source(
+    tagged_path: &TaggedPath,
+    href: &str,
+) -> Option<(path::PathBuf, RangeInclusive<usize>)> {
+    let (page, fragment) = match href.split_once('#') {
+        Some((page, fragment)) => (page, fragment),
+        None => (href, ""),
+    };
+    let mut bounds = fragment
+        .split('-')
+        .map(|l| l.trim_start_matches('L').parse::<usize>().ok());
+    let start = bounds.next().flatten().unwrap_or(1);
+
+    // Resolving the link relative to the page gives a path under <doc root>/src/<crate>/
+    let rendered = linked_path(tagged_path, page);
+    // Newer rustdoc links to the first line of a function rather than to all of its lines
+    let end = match bounds.next().flatten() {
+        Some(end) => end,
+        None if !fragment.is_empty() => rendered_source_code(&rendered)
+            .and_then(|code| item_end(&code, start))
+            .unwrap_or(start),
+        None => start,
+    };
+
+    Some((rendered, start..=end))
+}
+
+/**
+ * The last line of the item starting on the given line of some source code. The item ends
+ * at the brace closing its body or at a semicolon outside of any brackets: comments and
+ * string and char literals are skipped while matching.
+ */
+fn item_end(code: &str, start: usize) -> Option<usize> {
+    let rest: String = code
+        .split_inclusive('\n')
+        .skip(start.checked_sub(1)?)
+        .collect();
+    let mut chars = rest.chars().peekable();
+    let (mut line, mut depth) = (start, 0);
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                let mut prev = chars.next();
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        line += 1;
+                    } else if c == '/' && prev == Some('*') {
+                        break;
+                    }
+                    prev = Some(c);
+                }
+            }
+            '"' => {
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' if chars.next() == Some('\n') => line += 1,
+                        '\n' => line += 1,
+                        _ => (),
+                    }
+                }
+            }
+            // A char literal rather than a lifetime
+            '\'' => {
+                let mut ahead = chars.clone();
+                match (ahead.next(), ahead.next()) {
+                    (Some('\\'), _) => {
+                        chars.next();
+                        chars.next();
+                        while chars.next_if(|&c| c != '\'').is_some() {}
+                        chars.next();
+                    }
+                    (Some(_), Some('\'')) => {
+                        chars.next();
+                        chars.next();
+                    }
+                    _ => (),
+                }
+            }
+            '{' | '(' | '[' => depth += 1,
+            '}' | ')' | ']' => {
+                depth -= 1;
+                if depth == 0 && c == '}' {
+                    return Some(line);
+                }
+            }
+            ';' if depth == 0 => return Some(line),
+            _ => (),
+        }
+    }
+
+    None
+}
+
+/**
+ * The TaggedPath for the doc page that an href on the page of a tagged path links to. Links
+ * to a method anchor on the page of a type resolve to that method. Returns None for links
+ * to anything other than item and module pages under the same doc root.
+ */
+pub fn resolve_link(tagged_path: &TaggedPath, href: &str) -> Option<TaggedPath> {
+    let (page, fragment) = match href.split_once('#') {
+        Some((page, fragment)) => (page, fragment),
+        None => (href, ""),
+    };
+    if page.is_empty() || page.contains("://") || !page.ends_with(".html") {
+        return None;
+    }
+
+    let root = tagged_path.root()?;
+    let linked = linked_path(tagged_path, page);
+    if !linked.starts_with(&root) || linked.starts_with(root.join("src")) || !linked.is_file() {
+        return None;
+    }
+
+    let mut tagged = TaggedPath::from(linked);
+    if matches!(tagged.tag, Tag::Unknown | Tag::Keyword) {
+        return None;
+    }
+    tagged.root = Some(root);
+    let method = fragment
+        .strip_prefix("method.")
+        .or_else(|| fragment.strip_prefix("tymethod."));
+    if let Some(m) = method {
+        tagged.tag = Tag::Method;
+        tagged.method_name = Some(m.to_string());
+    }
+
+    Some(tagged)
+}
+
+// Resolve the path of a (relative) link found on the page of a tagged path
+fn linked_path(tagged_path: &TaggedPath, page: &str) -> path::PathBuf {
+    let mut linked = path::PathBuf::from(tagged_path.dir());
+    for component in path::Path::new(page).components() {
+        match component {
+            path::Component::ParentDir => {
+                linked.pop();
+            }
+            path::Component::Normal(c) => linked.push(c),
+            _ => (),
+        }
+    }
+
+    linked
+}
+
+/**
+ * Map a rustdoc source page link (e.g. "../src/foo/lib.rs.html#10-20") found on the page
+ * of a tagged path back to the original source file and line that it was rendered from.
+ */
+pub fn original_source(tagged_path: &TaggedPath, href: &str) -> Option<Location> {
+    original_source_in(tagged_path, href, crate_source_dir)
+}
+
+// The source file that a rendered source page was generated from, given a way to find
+// the directory holding the original sources of a crate
+fn original_source_in(
+    tagged_path: &TaggedPath,
+    href: &str,
+    source_dir: impl Fn(&str) -> Option<path::PathBuf>,
+) -> Option<Location> {
+    let (rendered, lines) = rendered_source(tagged_path, href)?;
+    let relative = rendered
+        .strip_prefix(tagged_path.root()?.join("src"))
+        .ok()?;
+    let mut components = relative.components();
+    let crate_name = components.next()?.as_os_str().to_str()?;
+    let file = components
+        .as_path()
+        .to_str()?
+        .strip_suffix(".html")?
+        .to_string();
+    let file = source_dir(crate_name)?.join(file);
+
+    if file.is_file() {
+        Some(Location {
+            file,
+            line: *lines.start(),
+            column: 1,
+            lines,
+        })
+    } else {
+        None
+    }
+}
+
+// The directory that the source files of a crate are relative to in its rendered source
+fn crate_source_dir(crate_name: &str) -> Option<path::PathBuf> {
+    if STD_CRATES.contains(&crate_name) {
+        Some(
+            get_sys_root()?
+                .join(STD_SOURCE_PATH)
+                .join(crate_name)
+                .join("src"),
+        )
+    } else {
+        Some(get_crate_source_root(crate_name)?.parent()?.to_path_buf())
+    }
+}
+
+/**
+ * The entry point source file (lib.rs or main.rs) of a crate, used when there is
+ * no rustdoc output available for it. We look in the current workspace first and
+ * then in the cargo registry, using the version locked in Cargo.lock.
+ */
+pub fn get_crate_source_root(crate_name: &str) -> Option<path::PathBuf> {
+    let matches = |manifest: &path::Path| {
+        manifest_package_name(manifest).is_some_and(|n| n.replace('-', "_") == crate_name)
+    };
+
+    let crate_root = get_crate_root()?;
+    if matches(&crate_root.join("Cargo.toml")) {
+        return crate_entry_point(&crate_root);
+    }
+
+    // Other workspace members live alongside the lockfile, typically one or two levels down
+    let mut workspace_root = crate_root.clone();
+    while !workspace_root.join("Cargo.lock").is_file() && workspace_root.pop() {}
+    let mut dirs = vec![workspace_root.clone()];
+    for _ in 0..2 {
+        dirs = dirs
+            .iter()
+            .filter_map(|d| d.read_dir().ok())
+            .flat_map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()))
+            .filter(|p| p.is_dir() && !p.ends_with("target") && !p.ends_with(".git"))
+            .collect();
+        if let Some(dir) = dirs.iter().find(|d| matches(&d.join("Cargo.toml"))) {
+            return crate_entry_point(dir);
+        }
+    }
+
+    let pkg = Lockfile::find()?.package(crate_name)?.clone();
+    let registry = get_cargo_home()?.join("registry/src");
+    registry
+        .read_dir()
+        .ok()?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path().join(format!("{}-{}", pkg.name, pkg.version)))
+        .find(|dir| dir.is_dir())
+        .and_then(|dir| crate_entry_point(&dir))
+}
+
+fn crate_entry_point(dir: &path::Path) -> Option<path::PathBuf> {
+    vec!["src/lib.rs", "src/main.rs"]
+        .into_iter()
+        .map(|f| dir.join(f))
+        .find(|p| p.is_file())
+}
+
+// The package name from the [package] section of a Cargo.toml
+pub(crate) fn manifest_package_name(manifest: &path::Path) -> Option<String> {
+    let contents = fs::read_to_string(manifest).ok()?;
+    let mut in_package = false;
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_package = line == "[package]";
+        } else if in_package {
+            if let Some(("name", v)) = line.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                return Some(v.trim_matches('"').to_string());
+            }
+        }
+    }
+
+    None
+}
+
+fn get_cargo_home() -> Option<path::PathBuf> {
+    match env::var_os("CARGO_HOME") {
+        Some(dir) => Some(path::PathBuf::from(dir)),
+        None => Some(path::PathBuf::from(env::var_os("HOME")?).join(".cargo")),
+    }
+}
+
+pub(crate) fn get_crate_root() -> Option<path::PathBuf> {
     let mut cur_dir = env::current_dir().ok().unwrap();
     let cargo_toml = ffi::OsStr::new("Cargo.toml");
     let file_system_root = path::Path::new("/");
@@ -337,8 +1235,11 @@ mod tests {
     #[test_case("test_resources/foo/enum.elon.html", Tag::Enum)]
     #[test_case("test_resources/foo/fn.foo.html", Tag::Function)]
     #[test_case("test_resources/foo/macro.makrow.html", Tag::Macro)]
+    #[test_case("test_resources/foo/derive.Makrow.html", Tag::Macro)]
     #[test_case("test_resources/foo/index.html", Tag::Module)]
-    #[test_case("test_resources/foo/primative.ug.html", Tag::Primative)]
+    #[test_case("test_resources/foo/keyword.kw.html", Tag::Keyword)]
+    #[test_case("test_resources/foo/primitive.ug.html", Tag::Primitive)]
+    #[test_case("test_resources/foo/static.STATIK.html", Tag::Static)]
     #[test_case("test_resources/foo/struct.structural.html", Tag::Struct)]
     #[test_case("test_resources/foo/trait.fooable.html", Tag::Trait)]
     #[test_case("test_resources/foo/some_other_unknown.html", Tag::Unknown)]
@@ -349,9 +1250,136 @@ mod tests {
         assert_eq!(symbol_type, expected);
     }
 
+    #[test_case("test_resources/toolchain/project", 0, None; "stale from a parent rust-toolchain file")]
+    #[test_case("test_resources/diff", 0, Some("test_resources"); "no rust-toolchain files")]
+    #[test_case("test_resources/toolchain/project", u64::MAX, Some("test_resources"); "fresh")]
+    fn cached_sys_roots_are_invalidated(dir: &str, cached_at: u64, expected: Option<&str>) {
+        let contents = format!(
+            "other|\t{}\t/nowhere\n{}|\t{}\ttest_resources\n",
+            u64::MAX,
+            dir,
+            cached_at
+        );
+        let sources = toolchain_files(path::Path::new(dir));
+
+        assert_eq!(
+            cached_sys_root(&contents, &format!("{}|", dir), &sources),
+            expected.map(path::PathBuf::from)
+        );
+    }
+
+    #[test]
+    fn toolchain_files_are_found_in_parent_directories() {
+        assert_eq!(
+            toolchain_files(path::Path::new("test_resources/toolchain/project")),
+            vec![path::PathBuf::from(
+                "test_resources/toolchain/rust-toolchain.toml"
+            )]
+        );
+    }
+
+    #[test]
+    fn sys_root_cache_entries_are_replaced_and_capped() {
+        let mut contents = String::new();
+        for i in 0..SYSROOT_CACHE_ENTRIES + 5 {
+            let key = format!("dir-{}|", i % (SYSROOT_CACHE_ENTRIES + 2));
+            contents = updated_sys_root_cache(&contents, &key, path::Path::new("/root"), i as u64);
+        }
+        let lines: Vec<&str> = contents.lines().collect();
+
+        assert_eq!(lines.len(), SYSROOT_CACHE_ENTRIES);
+        assert_eq!(
+            lines.last(),
+            Some(&format!("dir-2|\t{}\t/root", SYSROOT_CACHE_ENTRIES + 4).as_str())
+        );
+        assert_eq!(lines.iter().filter(|l| l.starts_with("dir-2|")).count(), 1);
+    }
+
+    const DEPENDENCY_LOCKFILE: &str = r#"
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "alpha",
+ "beta",
+]
+
+[[package]]
+name = "alpha"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "shared-dep",
+]
+
+[[package]]
+name = "beta"
+version = "2.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "shared-dep",
+]
+
+[[package]]
+name = "shared-dep"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "leaf",
+]
+
+[[package]]
+name = "leaf"
+version = "0.1.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#;
+
+    #[test]
+    fn dependency_trees_are_rendered() {
+        let lockfile = Lockfile::parse(DEPENDENCY_LOCKFILE);
+        let doc_root = path::PathBuf::from("test_resources/deps");
+        let rows = DependencyTree::new(&lockfile, Some(doc_root)).rows();
+        let rows: Vec<(&str, &str)> = rows
+            .iter()
+            .map(|r| (r[0].as_ref(), r[1].as_ref()))
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                ("app v0.1.0", "The app."),
+                ("|-- alpha v1.0.0", "(no docs)"),
+                (
+                    "|   `-- shared-dep v0.2.0",
+                    "Shared between alpha and beta."
+                ),
+                ("|       `-- leaf v0.1.0", "(no docs)"),
+                ("`-- beta v2.0.0", "(no docs)"),
+                (
+                    "    `-- shared-dep v0.2.0 (*)",
+                    "Shared between alpha and beta."
+                ),
+            ]
+        );
+    }
+
+    #[test_case("u32", None, true; "primitive")]
+    #[test_case("array", None, true; "primitive without local docs")]
+    #[test_case("array", Some("test_resources/shadowed"), false; "shadowed by a local crate")]
+    #[test_case("u32", Some("test_resources/shadowed"), true; "not shadowed")]
+    #[test_case("foo", None, false; "not a primitive")]
+    fn primitives_are_detected(first: &str, cargo_root: Option<&str>, expected: bool) {
+        assert_eq!(
+            is_primitive(first, cargo_root.map(path::Path::new)),
+            expected
+        );
+    }
+
     #[test_case("std::fs::File", CrateType::StdLib, QueryType::Unknown, vec!["std", "fs", "File"])]
     #[test_case("std::path::PathBuf.file_name", CrateType::StdLib, QueryType::InstanceMethod, vec!["std", "path", "PathBuf", "file_name"])]
     #[test_case("foo::Foo.bar", CrateType::Cargo, QueryType::InstanceMethod, vec!["foo", "Foo", "bar"])]
+    #[test_case("i32.checked_add", CrateType::StdLib, QueryType::Primitive, vec!["std", "i32", "checked_add"])]
+    #[test_case("str", CrateType::StdLib, QueryType::Primitive, vec!["std", "str"])]
     fn locator_from_input(
         path: &str,
         crate_type: CrateType,
@@ -369,4 +1397,68 @@ mod tests {
             }
         )
     }
+
+    #[test_case("fn.foo.html", Some(("fn.foo.html", Tag::Function, None)); "item")]
+    #[test_case("struct.structural.html#method.new", Some(("struct.structural.html", Tag::Method, Some("new"))); "method")]
+    #[test_case("../foo/index.html", Some(("index.html", Tag::Module, None)); "module")]
+    #[test_case("struct.missing.html", None; "missing page")]
+    #[test_case("https://doc.rust-lang.org/std/index.html", None; "external")]
+    #[test_case("#method.new", None; "anchor on the same page")]
+    fn links_are_resolved(href: &str, expected: Option<(&str, Tag, Option<&str>)>) {
+        let mut tagged_path =
+            TaggedPath::from(path::PathBuf::from("test_resources/foo/enum.elon.html"));
+        tagged_path.root = Some(path::PathBuf::from("test_resources"));
+
+        let resolved = resolve_link(&tagged_path, href)
+            .map(|t| (t.file_name.clone(), t.tag.clone(), t.method_name.clone()));
+        let expected = expected.map(|(f, t, m)| (f.to_string(), t, m.map(String::from)));
+
+        assert_eq!(resolved, expected);
+    }
+
+    #[test_case("../src/krate/point.rs.html#3-6", 3..=6; "line range")]
+    #[test_case("../src/krate/point.rs.html#3", 3..=6; "single line struct")]
+    #[test_case("../src/krate/point.rs.html#13", 13..=15; "single line method")]
+    #[test_case("../src/krate/point.rs.html#8", 8..=16; "single line impl")]
+    #[test_case("../src/krate/missing.rs.html#8", 8..=8; "missing page")]
+    fn rendered_source_lines_span_the_item(href: &str, lines: RangeInclusive<usize>) {
+        let tagged_path = TaggedPath::from(path::PathBuf::from(
+            "test_resources/source/struct.Point.html",
+        ));
+
+        let (page, found) = rendered_source(&tagged_path, href).unwrap();
+        assert!(page.ends_with(href.split('#').next().unwrap().trim_start_matches("../")));
+        assert_eq!(found, lines);
+    }
+
+    #[test_case("pub fn f() -> char {\n    '}'\n}\n", 3; "char literal")]
+    #[test_case("pub fn f<'a>(s: &'a str) {\n    s.find(\"{\");\n}\n", 3; "lifetime and string")]
+    #[test_case("pub fn f(); // {\nfn g() {}\n", 1; "declaration")]
+    #[test_case("#[derive(Debug)]\npub struct T(u8);\n", 2; "tuple struct")]
+    #[test_case("pub enum E {\n    /* } */ A,\n}\n", 3; "block comment")]
+    fn item_ends_are_found(code: &str, end: usize) {
+        assert_eq!(item_end(code, 1), Some(end));
+    }
+
+    #[test_case("struct.Foo.html", "../src/krate/lib.rs.html#8-16", 8..=16)]
+    #[test_case("a/fn.foo.html", "../../src/krate/a.rs.html#L20", 20..=20)]
+    #[test_case("index.html", "../src/krate/lib.rs.html", 1..=1)]
+    fn source_links_map_to_original_files(page: &str, href: &str, lines: RangeInclusive<usize>) {
+        let root = path::PathBuf::from("test_resources");
+        let tagged_path = TaggedPath::with_root(root.join("krate").join(page), &root);
+        let sources = path::Path::new("test_resources/extract/krate");
+        let file = href.split('#').next().unwrap().trim_start_matches("../");
+        let file = file
+            .trim_start_matches("src/krate/")
+            .trim_end_matches(".html");
+
+        let loc = original_source_in(&tagged_path, href, |name| {
+            assert_eq!(name, "krate");
+            Some(sources.to_path_buf())
+        })
+        .unwrap();
+        assert_eq!(loc.file, sources.join(file));
+        assert_eq!((loc.line, loc.column), (*lines.start(), 1));
+        assert_eq!(loc.lines, lines);
+    }
 }
@@ -0,0 +1,372 @@
+/*!
+ * Read the resolved dependency graph of the current workspace from its Cargo.lock
+ */
+use crate::locate::{get_crate_root, manifest_package_name};
+use std::{fs, path};
+
+/// How a package in the lockfile relates to the current workspace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    /// A member of the workspace itself
+    Workspace,
+    /// A local path dependency from outside of the workspace
+    Path,
+    /// A dependency of one of the workspace members
+    Direct,
+    /// A dependency that is only pulled in by another dependency
+    Transitive,
+}
+
+/// A single resolved package from a Cargo.lock file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Package {
+    /// The package name as written in Cargo.toml
+    pub name: String,
+    /// The resolved version of the package
+    pub version: String,
+    /// Where the package was fetched from (workspace members have no source)
+    pub source: Option<String>,
+    /// The name and (if more than one version is locked) version of each dependency
+    pub dependencies: Vec<(String, Option<String>)>,
+}
+
+impl Package {
+    /// The name of the directory that rustdoc writes docs for this package into
+    pub fn doc_dir_name(&self) -> String {
+        self.name.replace('-', "_")
+    }
+}
+
+/// The packages listed in a Cargo.lock file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    /// Every locked package in the order that they appear in the file
+    pub packages: Vec<Package>,
+    /// The names of the workspace members if they are known from the workspace manifest
+    pub members: Vec<String>,
+}
+
+impl Lockfile {
+    /// Find and parse the Cargo.lock for the current workspace
+    pub fn find() -> Option<Self> {
+        let mut dir = get_crate_root()?;
+        loop {
+            let candidate = dir.join("Cargo.lock");
+            if candidate.is_file() {
+                let mut lockfile = Self::read(&candidate)?;
+                lockfile.members = workspace_member_names(&dir);
+                return Some(lockfile);
+            }
+            if !dir.pop() {
+                return None;
+            }
+        }
+    }
+
+    /// Parse the Cargo.lock file at the given path
+    pub fn read(path: &path::Path) -> Option<Self> {
+        fs::read_to_string(path).ok().map(|s| Self::parse(&s))
+    }
+
+    /// Parse the contents of a Cargo.lock file
+    pub fn parse(contents: &str) -> Self {
+        let mut packages: Vec<Package> = vec![];
+        let mut current: Option<Package> = None;
+        let mut in_dependencies = false;
+
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') && !in_dependencies {
+                packages.extend(current.take());
+                if line == "[[package]]" {
+                    current = Some(Package {
+                        name: String::new(),
+                        version: String::new(),
+                        source: None,
+                        dependencies: vec![],
+                    });
+                }
+                continue;
+            }
+
+            let pkg = match current.as_mut() {
+                Some(pkg) => pkg,
+                None => continue,
+            };
+
+            if in_dependencies {
+                if line == "]" {
+                    in_dependencies = false;
+                } else if let Some(dep) = unquote(line.trim_end_matches(',')) {
+                    let mut parts = dep.split_whitespace();
+                    let name = parts.next().unwrap_or_default().to_string();
+                    pkg.dependencies
+                        .push((name, parts.next().map(String::from)));
+                }
+                continue;
+            }
+
+            match line.split_once(" = ") {
+                Some(("name", v)) => pkg.name = unquote(v).unwrap_or_default().to_string(),
+                Some(("version", v)) => pkg.version = unquote(v).unwrap_or_default().to_string(),
+                Some(("source", v)) => pkg.source = unquote(v).map(String::from),
+                Some(("dependencies", "[")) => in_dependencies = true,
+                _ => (),
+            }
+        }
+        packages.extend(current.take());
+
+        Lockfile {
+            packages,
+            members: vec![],
+        }
+    }
+
+    /**
+     * The package with the given name (or doc directory name). If more than one version
+     * is locked then we use the version that the workspace depends on, falling back to
+     * the version depended on by any other package.
+     */
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        let mut candidates = self
+            .packages
+            .iter()
+            .filter(|p| p.name == name || p.doc_dir_name() == name);
+        let first = candidates.next()?;
+        if candidates.next().is_none() {
+            return Some(first);
+        }
+
+        self.workspace_members()
+            .into_iter()
+            .chain(self.packages.iter())
+            .flat_map(|p| p.dependencies.iter())
+            .find(|(dep, _)| *dep == first.name)
+            .and_then(|(dep, version)| self.dependency(dep, version))
+            .or(Some(first))
+    }
+
+    /// The package that a dependency entry of another package refers to
+    pub fn dependency(&self, name: &str, version: &Option<String>) -> Option<&Package> {
+        self.packages
+            .iter()
+            .find(|p| p.name == name && version.as_ref().is_none_or(|v| &p.version == v))
+    }
+
+    /// The members of the workspace
+    pub fn workspace_members(&self) -> Vec<&Package> {
+        self.packages.iter().filter(|p| self.is_member(p)).collect()
+    }
+
+    // Packages without a source are either workspace members or path dependencies: we can
+    // only tell them apart if we know the members from the workspace manifest
+    fn is_member(&self, pkg: &Package) -> bool {
+        pkg.source.is_none() && (self.members.is_empty() || self.members.contains(&pkg.name))
+    }
+
+    /// How the package with the given name (or doc directory name) relates to the workspace
+    pub fn origin(&self, name: &str) -> Option<Origin> {
+        let pkg = self.package(name)?;
+        if self.is_member(pkg) {
+            return Some(Origin::Workspace);
+        }
+        if pkg.source.is_none() {
+            return Some(Origin::Path);
+        }
+
+        let direct = self
+            .workspace_members()
+            .iter()
+            .flat_map(|m| m.dependencies.iter())
+            .any(|(dep, _)| *dep == pkg.name);
+
+        Some(if direct {
+            Origin::Direct
+        } else {
+            Origin::Transitive
+        })
+    }
+}
+
+/**
+ * The package names of the members of the workspace whose root manifest is in dir: the
+ * root package itself along with each entry (or directory matched by a trailing `*`) in
+ * the members list of its [workspace] table.
+ */
+fn workspace_member_names(dir: &path::Path) -> Vec<String> {
+    let manifest = dir.join("Cargo.toml");
+    let mut names: Vec<String> = manifest_package_name(&manifest).into_iter().collect();
+    let contents = fs::read_to_string(&manifest).unwrap_or_default();
+
+    let mut member_dirs: Vec<path::PathBuf> = vec![];
+    for pattern in workspace_members_list(&contents) {
+        match pattern.strip_suffix('*') {
+            Some(parent) => {
+                let mut dirs: Vec<path::PathBuf> = fs::read_dir(dir.join(parent))
+                    .map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())).collect())
+                    .unwrap_or_default();
+                dirs.sort();
+                member_dirs.extend(dirs);
+            }
+            None => member_dirs.push(dir.join(pattern)),
+        }
+    }
+
+    names.extend(
+        member_dirs
+            .iter()
+            .filter_map(|d| manifest_package_name(&d.join("Cargo.toml"))),
+    );
+    names
+}
+
+// The entries of the members array in the [workspace] table of a manifest
+fn workspace_members_list(manifest: &str) -> Vec<String> {
+    let mut in_workspace = false;
+    let mut in_members = false;
+    let mut members = vec![];
+
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') && !in_members {
+            in_workspace = line == "[workspace]";
+            continue;
+        }
+        if !in_workspace {
+            continue;
+        }
+
+        let values = match line.split_once('=') {
+            Some((k, v)) if !in_members && k.trim() == "members" => {
+                in_members = true;
+                v.trim().trim_start_matches('[')
+            }
+            _ if in_members => line,
+            _ => continue,
+        };
+        if values.contains(']') {
+            in_members = false;
+        }
+        members.extend(
+            values
+                .split([',', ']'])
+                .filter_map(unquote)
+                .map(String::from),
+        );
+    }
+
+    members
+}
+
+fn unquote(s: &str) -> Option<&str> {
+    s.trim().strip_prefix('"')?.strip_suffix('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCKFILE: &str = r#"# This file is automatically @generated by Cargo.
+version = 3
+
+[[package]]
+name = "rocdoc"
+version = "0.1.2"
+dependencies = [
+ "select",
+ "term_size",
+]
+
+[[package]]
+name = "select"
+version = "0.5.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = [
+ "bit-set 0.5.1",
+]
+
+[[package]]
+name = "bit-set"
+version = "0.5.1"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "term_size"
+version = "0.3.2"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[metadata]
+"checksum foo" = "bar"
+"#;
+
+    #[test]
+    fn packages_are_parsed() {
+        let lock = Lockfile::parse(LOCKFILE);
+        let names: Vec<&str> = lock.packages.iter().map(|p| p.name.as_ref()).collect();
+
+        assert_eq!(names, vec!["rocdoc", "select", "bit-set", "term_size"]);
+        assert_eq!(
+            lock.package("select").unwrap().dependencies,
+            vec![("bit-set".to_string(), Some("0.5.1".to_string()))]
+        );
+        assert_eq!(lock.package("bit_set").unwrap().version, "0.5.1");
+    }
+
+    #[test]
+    fn origins_are_resolved() {
+        let lock = Lockfile::parse(LOCKFILE);
+
+        assert_eq!(lock.origin("rocdoc"), Some(Origin::Workspace));
+        assert_eq!(lock.origin("select"), Some(Origin::Direct));
+        assert_eq!(lock.origin("bit_set"), Some(Origin::Transitive));
+        assert_eq!(lock.origin("missing"), None);
+    }
+
+    const MULTI_VERSION_LOCKFILE: &str = r#"
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = [
+ "bitflags 2.4.0",
+ "patched",
+]
+
+[[package]]
+name = "bitflags"
+version = "1.3.2"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "bitflags"
+version = "2.4.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "patched"
+version = "0.3.0"
+dependencies = [
+ "bitflags 1.3.2",
+]
+
+[[package]]
+name = "tool"
+version = "0.1.0"
+"#;
+
+    #[test]
+    fn versions_are_resolved_through_dependents() {
+        let lock = Lockfile::parse(MULTI_VERSION_LOCKFILE);
+
+        assert_eq!(lock.package("bitflags").unwrap().version, "2.4.0");
+    }
+
+    #[test]
+    fn path_dependencies_are_not_workspace_members() {
+        let mut lock = Lockfile::parse(MULTI_VERSION_LOCKFILE);
+        lock.members = workspace_member_names(path::Path::new("test_resources/workspace"));
+
+        assert_eq!(lock.members, vec!["app", "app-core", "tool"]);
+        assert_eq!(lock.origin("app"), Some(Origin::Workspace));
+        assert_eq!(lock.origin("tool"), Some(Origin::Workspace));
+        assert_eq!(lock.origin("patched"), Some(Origin::Path));
+        assert_eq!(lock.origin("bitflags"), Some(Origin::Direct));
+    }
+}
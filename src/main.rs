@@ -1,7 +1,15 @@
-use clap::Clap;
+use clap::{ArgSettings, Clap};
+use rocdoc::complete;
+use rocdoc::cursor;
+use rocdoc::diff;
+use rocdoc::explain;
+use rocdoc::extract::Location;
 use rocdoc::locate;
 use rocdoc::parse;
-use std::process;
+use rocdoc::server;
+use rocdoc::source;
+use rocdoc::tui;
+use std::{env, path, process};
 
 const CRATE_ROOT_QUERIES: &[&'static str] = &[".", "crate"];
 
@@ -14,43 +22,374 @@ struct Options {
     #[clap(short = 'l', long = "list")]
     list: bool,
 
+    /// with --list, recurse through child modules and show them as a tree
+    #[clap(long = "tree")]
+    tree: bool,
+
+    /// with --tree, the maximum number of levels of child modules to show
+    #[clap(long = "depth")]
+    depth: Option<usize>,
+
+    /// with --tree, only count items of this kind (struct, enum, trait, fn, macro...)
+    #[clap(long = "kind", number_of_values = 1)]
+    kinds: Vec<String>,
+
     /// open the selected doc page in the browser using full rustdoc
     #[clap(short = 'o', long = "open")]
     open_in_browser: bool,
 
+    /// only show the input patterns accepted by a macro
+    #[clap(long = "arms")]
+    arms: bool,
+
+    /// show the declared value of constants and statics in module listings
+    #[clap(long = "values")]
+    values: bool,
+
+    /// look up a language keyword in the std docs (lists all keywords without a query)
+    #[clap(short = 'k', long = "keyword")]
+    keyword: bool,
+
+    /// show the dependency tree of the current project along with crate summaries
+    #[clap(long = "deps")]
+    deps: bool,
+
+    /// list every compiler error code along with a one line summary
+    #[clap(long = "errors")]
+    errors: bool,
+
+    /// only show the code examples from a compiler error code explanation
+    #[clap(long = "examples")]
+    examples: bool,
+
+    /// use the std docs from this rustup toolchain (defaults to $ROC_TOOLCHAIN)
+    #[clap(long = "toolchain")]
+    toolchain: Option<String>,
+
+    /// list the installed toolchains that have std docs available
+    #[clap(long = "toolchains")]
+    toolchains: bool,
+
+    /// print the file:line:col where the item is defined instead of its docs
+    #[clap(long = "where")]
+    show_location: bool,
+
+    /// open the file where the item is defined in $EDITOR at the line it is defined on
+    #[clap(long = "edit")]
+    edit: bool,
+
+    /// show the source code of the item (including the body of methods) instead of its docs
+    #[clap(long = "source")]
+    source: bool,
+
+    /// look up the symbol at a file:line:col position in a source file (for editor integration)
+    #[clap(long = "at")]
+    at: Option<String>,
+
+    /// run as a server for editor plugins, answering JSON-RPC requests one per line on stdin
+    #[clap(long = "serve")]
+    serve: bool,
+
+    /// with --serve, listen for connections on this unix socket instead of using stdin
+    #[clap(long = "socket")]
+    socket: Option<String>,
+
+    /// print a completion script for the given shell (bash, zsh or fish)
+    #[clap(long = "completions", possible_values = &["bash", "zsh", "fish"])]
+    completions: Option<String>,
+
+    /// complete a partial query path (used by the shell completion scripts)
+    #[clap(long = "complete", hidden = true, setting = ArgSettings::AllowEmptyValues)]
+    complete: Option<String>,
+
+    /// browse the docs interactively in a full screen terminal UI, starting from the query path
+    #[clap(short = 'i', long = "interactive")]
+    interactive: bool,
+
     /// grep the resulting output to only show lines matching this query
     #[clap(short = 'g', long = "grep")]
     grep: Option<String>,
 
+    #[clap(subcommand)]
+    command: Option<Command>,
+
     /// <crate/mod>[::<symbol>[.<method>]]
-    query: String,
+    query: Option<String>,
+}
+
+#[derive(Clap, Debug)]
+enum Command {
+    /// compare the documented public API of two toolchains or two doc directories
+    /// (exits with 1 if there are any changes and 2 on error)
+    Diff(DiffOptions),
+}
+
+#[derive(Clap, Debug)]
+struct DiffOptions {
+    /// a toolchain to compare (given twice: older then newer)
+    #[clap(long = "toolchain", number_of_values = 1)]
+    toolchains: Vec<String>,
+
+    /// the format of the report
+    #[clap(long = "format", default_value = "text", possible_values = &["text", "json"])]
+    format: String,
+
+    /// <module> when comparing toolchains or <doc-dir-a> <doc-dir-b> <crate>
+    args: Vec<String>,
 }
 
 fn main() {
     let opts: Options = Options::parse();
 
-    if CRATE_ROOT_QUERIES.contains(&opts.query.as_ref()) {
+    if let Some(Command::Diff(diff_opts)) = &opts.command {
+        run_diff(diff_opts);
+    }
+
+    let toolchain = opts.toolchain.clone().or_else(|| {
+        env::var(locate::TOOLCHAIN_ENV_VAR)
+            .ok()
+            .filter(|t| !t.is_empty())
+    });
+    if let Some(toolchain) = toolchain {
+        if let Err(e) = locate::set_toolchain(&toolchain) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+
+    if let Some(shell) = &opts.completions {
+        print!("{}", complete::shell_script(shell).unwrap_or_default());
+        return;
+    }
+
+    if let Some(partial) = &opts.complete {
+        complete::complete(partial)
+            .iter()
+            .for_each(|c| println!("{}", c));
+        return;
+    }
+
+    if opts.serve {
+        let res = match &opts.socket {
+            Some(socket) => server::serve_socket(path::Path::new(socket)),
+            None => server::serve_stdio(),
+        };
+        if let Err(e) = res {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    if opts.toolchains {
+        if let Err(e) = locate::list_toolchains() {
+            eprintln!("{}", e);
+        }
+        return;
+    }
+
+    if opts.deps {
+        if let Err(e) = locate::show_dependency_tree() {
+            eprintln!("{}", e);
+        }
+        return;
+    }
+
+    if opts.errors {
+        if let Err(e) = explain::list_error_codes() {
+            eprintln!("{}", e);
+        }
+        return;
+    }
+
+    if opts.keyword {
+        show_keyword(opts.query, opts.grep);
+        return;
+    }
+
+    if opts.interactive {
+        run_interactive(opts.query);
+        return;
+    }
+
+    let query = match (opts.at.as_deref(), opts.query) {
+        (Some(position), _) => {
+            let resolved = cursor::parse_position(position)
+                .and_then(|(file, line, col)| cursor::query_at(&file, line, col));
+            match resolved {
+                Some(q) => q,
+                None => {
+                    println!("unable to resolve the symbol at {}", position);
+                    process::exit(1);
+                }
+            }
+        }
+        (None, Some(q)) => q,
+        (None, None) => {
+            println!("no query path given");
+            process::exit(1);
+        }
+    };
+
+    if explain::is_error_code(&query) {
+        explain::show_explanation(&query, opts.grep, opts.examples);
+        return;
+    }
+
+    if CRATE_ROOT_QUERIES.contains(&query.as_ref()) {
         if let Err(e) = locate::list_known_crates() {
             eprintln!("{}", e);
         }
         return;
     }
 
-    let locator = locate::Locator::new(opts.query);
-    let tagged_path = match locator.determine_tagged_path() {
+    let tagged_path = resolve_query(&query);
+
+    if opts.show_location || opts.edit {
+        let location = tagged_path
+            .and_then(|p| parse::DocParser::new(p).source_location())
+            .or_else(|| source::source_location(&query));
+
+        match location {
+            Some(loc) if opts.edit => open_in_editor(&loc),
+            Some(loc) => println!("{}", loc),
+            None => {
+                println!("unable to locate the source of {}", query);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    if opts.source {
+        if !source::show_item_source(&query, tagged_path) {
+            println!("unable to find the source of {}", query);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let tagged_path = match tagged_path {
         Some(p) => p,
         None => {
-            println!("unable to resolve query path");
-            process::exit(1);
+            if !source::show_from_source(&query, opts.grep) {
+                println!("unable to resolve query path");
+                process::exit(1);
+            }
+            return;
         }
     };
 
     if opts.open_in_browser {
         open_in_browser(tagged_path);
+    } else if opts.list && opts.tree {
+        parse::DocParser::new(tagged_path).show_module_tree(opts.depth, &opts.kinds);
     } else if opts.list {
         parse::DocParser::new(tagged_path).show_child_modules();
+    } else if opts.arms {
+        parse::DocParser::new(tagged_path).show_macro_arms();
     } else {
-        parse::DocParser::new(tagged_path).parse_and_print(opts.grep);
+        parse::DocParser::new(tagged_path).parse_and_print(opts.grep, opts.values);
+    }
+}
+
+fn run_diff(opts: &DiffOptions) -> ! {
+    let res = match opts.args.as_slice() {
+        [module] => diff::toolchain_diff(&opts.toolchains, module),
+        [before, after, krate] if opts.toolchains.is_empty() => {
+            diff::diff_trees(path::Path::new(before), path::Path::new(after), krate)
+        }
+        _ => {
+            eprintln!("usage: roc diff --toolchain <a> --toolchain <b> <module>");
+            eprintln!("       roc diff <doc-dir-a> <doc-dir-b> <crate>");
+            process::exit(2);
+        }
+    };
+
+    match res {
+        Ok(changes) => {
+            if opts.format == "json" {
+                println!("{}", diff::changes_as_json(&changes));
+            } else {
+                println!("{}", diff::render_changes(&changes));
+            }
+            process::exit(if changes.is_empty() { 0 } else { 1 });
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    }
+}
+
+// Queries that don't name an item fall back to the keyword pages (use --keyword for those
+// sharing a name with a primitive type such as fn)
+fn resolve_query(query: &str) -> Option<locate::TaggedPath> {
+    locate::Locator::try_new(query.to_string())
+        .and_then(|l| l.determine_tagged_path())
+        .or_else(|| locate::keyword_tagged_path(query))
+}
+
+fn run_interactive(query: Option<String>) {
+    let start = match &query {
+        Some(q) => resolve_query(q),
+        None => locate::crate_index_tagged_path().or_else(locate::std_index_tagged_path),
+    };
+
+    let start = match start {
+        Some(p) => p,
+        None => {
+            println!("unable to resolve query path");
+            process::exit(1);
+        }
+    };
+
+    if let Err(e) = tui::run(start, open_in_browser) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn show_keyword(keyword: Option<String>, grep: Option<String>) {
+    let tagged_path = match keyword {
+        Some(k) => locate::keyword_tagged_path(&k),
+        None => locate::std_index_tagged_path(),
+    };
+
+    match tagged_path {
+        Some(p) if p.tag == locate::Tag::Keyword => {
+            parse::DocParser::new(p).parse_and_print(grep, false)
+        }
+        Some(p) => parse::DocParser::new(p).show_keywords(),
+        None => {
+            println!("unable to locate std keyword documentation");
+            process::exit(1);
+        }
+    }
+}
+
+fn open_in_editor(loc: &Location) {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let mut cmd = process::Command::new(program);
+    cmd.args(words);
+
+    // VS Code style editors take a file:line:col argument rather than +line
+    let name = path::Path::new(program)
+        .file_name()
+        .and_then(|n| n.to_str());
+    if matches!(name, Some("code") | Some("codium")) {
+        cmd.arg("-g").arg(loc.to_string());
+    } else {
+        cmd.arg(format!("+{}", loc.line)).arg(&loc.file);
+    }
+
+    if let Err(e) = cmd.status() {
+        eprintln!("failed to run '{}': {}", editor, e);
+        process::exit(1);
     }
 }
 
@@ -2,8 +2,10 @@
  * Parse the contents of rustdoc generated HTML files
  */
 use crate::{
+    extract::Location,
     locate, pprint,
     pprint::{header, ENUM_HEADING_COLOR, SECTION_HEADING_COLOR},
+    source::select_lines,
 };
 use grep::{
     regex::RegexMatcher,
@@ -12,35 +14,113 @@ use grep::{
 use select::{
     document::Document,
     node::Node,
-    predicate::{And, Class, Name, Not},
+    predicate::{And, Attr, Class, Name, Not, Or},
 };
-use std::{error::Error, fs};
+use std::{error::Error, fs, io, path, rc::Rc};
+
+// The id prefixes of the headings for the members of a type that can be queried directly
+const MEMBER_ID_PREFIXES: &[&str] = &["method.", "tymethod.", "associatedconstant."];
+
+// The module index sections that we count items from, along with the kind of item they hold
+const MODULE_ITEM_SECTIONS: &[(&str, &str)] = &[
+    ("structs", "struct"),
+    ("enums", "enum"),
+    ("unions", "union"),
+    ("traits", "trait"),
+    ("types", "type"),
+    ("functions", "fn"),
+    ("macros", "macro"),
+    ("constants", "constant"),
+    ("statics", "static"),
+];
+
+// A module along with its child modules, as shown by the module tree listing
+struct ModuleNode {
+    name: String,
+    summary: String,
+    counts: Vec<(&'static str, usize)>,
+    children: Vec<ModuleNode>,
+}
+
+impl ModuleNode {
+    fn has_items(&self) -> bool {
+        !self.counts.is_empty() || self.children.iter().any(|c| c.has_items())
+    }
+
+    fn add_rows(&self, indent: usize, rows: &mut Vec<Vec<String>>) {
+        let counts = self
+            .counts
+            .iter()
+            .map(|(kind, n)| format!("{} {}", n, kind))
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        rows.push(vec![
+            format!("{}{}", "  ".repeat(indent), self.name),
+            format!("[{}]", counts),
+            self.summary.clone(),
+        ]);
+        self.children
+            .iter()
+            .for_each(|c| c.add_rows(indent + 1, rows));
+    }
+}
+
+/// The parts of a documented item that make up its public API
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiSignature {
+    /// The declaration block of the item (empty for modules)
+    pub declaration: String,
+    /// Whether the item itself has been marked as deprecated
+    pub deprecated: bool,
+    /// The name and signature of each inherent method (and whether it is deprecated)
+    pub methods: Vec<(String, String, bool)>,
+    /// The implemented trait and full impl header of each trait implementation
+    pub trait_impls: Vec<(String, String)>,
+    /// The name and declaration of each enum variant
+    pub variants: Vec<(String, String)>,
+}
 
 /**
  * Parses generated HTML output from rustdoc to give summarised results.
  */
 pub struct DocParser {
-    contents: Document,
+    contents: Rc<Document>,
     tag: locate::Tag,
     method_name: Option<String>,
+    tagged_path: locate::TaggedPath,
 }
 
 impl DocParser {
     /// Create a new DocParser rooted at the given tagged search path
     pub fn new(tagged_path: locate::TaggedPath) -> Self {
         let file_name = tagged_path.file_name.clone();
-        let file = fs::File::open(tagged_path.path())
-            .expect(&format!("unable to open file: {}", file_name));
-        let contents = Document::from_read(file).expect(&format!(
-            "unable to parse rustdoc generated HTML file: {}",
-            file_name
-        ));
+        DocParser::try_new(tagged_path).unwrap_or_else(|e| {
+            panic!(
+                "unable to parse rustdoc generated HTML file: {}: {}",
+                file_name, e
+            )
+        })
+    }
+
+    /// Create a new DocParser, returning an error if the page can't be read
+    pub fn try_new(tagged_path: locate::TaggedPath) -> io::Result<Self> {
+        let contents = Document::from_read(fs::File::open(tagged_path.path())?)?;
 
-        return DocParser {
-            contents,
+        Ok(DocParser::from_document(tagged_path, contents))
+    }
+
+    /// Create a new DocParser for a tagged path whose file has already been parsed
+    pub fn from_document(
+        tagged_path: locate::TaggedPath,
+        contents: impl Into<Rc<Document>>,
+    ) -> Self {
+        DocParser {
+            contents: contents.into(),
             tag: tagged_path.tag.clone(),
             method_name: tagged_path.method_name.clone(),
-        };
+            tagged_path,
+        }
     }
 
     /// Instead of parsing the contents of the search result, show child modules instead
@@ -54,9 +134,224 @@ impl DocParser {
         println!("{}", s);
     }
 
+    /**
+     * Recurse through the child modules of this module, showing an indented tree of
+     * modules along with their item counts and summaries. Recursion stops after
+     * `depth` levels and `kinds` can be used to only count certain kinds of item.
+     */
+    pub fn show_module_tree(&self, depth: Option<usize>, kinds: &[String]) {
+        let name = self
+            .tagged_path
+            .module_path()
+            .last()
+            .cloned()
+            .unwrap_or_default();
+        let dir = path::PathBuf::from(self.tagged_path.dir());
+        let tree = module_node(&self.contents, &dir, name, depth, kinds);
+
+        if !kinds.is_empty() && !tree.has_items() {
+            println!("No matching items found");
+            return;
+        }
+
+        let mut rows: Vec<Vec<String>> = vec![];
+        tree.add_rows(0, &mut rows);
+        println!("{}", pprint::Table::from_rows(rows).as_string());
+    }
+
+    /// Where the item (or method) documented on this page is defined in its original source
+    pub fn source_location(&self) -> Option<Location> {
+        locate::original_source(&self.tagged_path, &self.source_link()?)
+    }
+
+    /**
+     * The source code of this item (or method) along with the file that it was read from.
+     * The original source file is used when it is available locally, otherwise the code is
+     * taken from the source page rendered by rustdoc.
+     */
+    pub fn item_source(&self) -> Option<(path::PathBuf, String)> {
+        let href = self.source_link()?;
+        if let Some(loc) = locate::original_source(&self.tagged_path, &href) {
+            let code = select_lines(&fs::read_to_string(&loc.file).ok()?, loc.lines)?;
+            return Some((loc.file, code));
+        }
+
+        let (page, lines) = locate::rendered_source(&self.tagged_path, &href)?;
+        let code = rendered_source_code(&page)?;
+
+        Some((page, select_lines(&code, lines)?))
+    }
+
+    // The href of the rustdoc source page link for this item, or for the method if this
+    // parser is for a method. The href is relative to the page being parsed.
+    fn source_link(&self) -> Option<String> {
+        let link = match &self.method_name {
+            Some(m) => {
+                let node = self.member_node(m)?;
+
+                // Older rustdoc puts the link inside the method heading, newer next to it
+                node.find(is_source_link)
+                    .next()
+                    .or_else(|| node.parent()?.find(is_source_link).next())
+            }
+            None => self.contents.find(is_source_link).next(),
+        };
+
+        link.and_then(|a| a.attr("href")).map(String::from)
+    }
+
+    /// The target of every link in the main content of the page, in the order they appear
+    pub fn links(&self) -> Vec<String> {
+        let main = self
+            .contents
+            .find(Attr("id", "main-content"))
+            .chain(self.contents.find(Attr("id", "main")))
+            .next();
+        let anchors: Vec<Node> = match main {
+            Some(node) => node.find(Name("a")).collect(),
+            None => self.contents.find(Name("a")).collect(),
+        };
+
+        let mut links: Vec<String> = vec![];
+        for href in anchors.iter().filter_map(|a| a.attr("href")) {
+            if !links.iter().any(|l| l == href) {
+                links.push(href.to_string());
+            }
+        }
+
+        links
+    }
+
+    /// The names of every method documented on this page, including trait methods
+    pub fn method_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .contents
+            .find(Attr("id", ()))
+            .filter_map(|n| {
+                let id = n.attr("id")?;
+                id.strip_prefix("method.")
+                    .or_else(|| id.strip_prefix("tymethod."))
+                    .map(String::from)
+            })
+            .collect();
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// List every keyword documented in the std index page along with its summary
+    pub fn show_keywords(&self) {
+        let s = if let Some(ks) = self.table_with_header("keywords", &None) {
+            ks
+        } else {
+            "No keywords found".into()
+        };
+
+        println!("{}", s);
+    }
+
+    /// Instead of parsing the contents of a macro page, show the input patterns it accepts
+    pub fn show_macro_arms(&self) {
+        println!("{}", self.macro_arm_lines());
+    }
+
+    // The input patterns of a macro_rules! macro, or the helper attributes of a derive macro
+    fn macro_arm_lines(&self) -> String {
+        let decl = self.extract_type_declaration();
+        let name = self.tagged_path.item_name().unwrap_or_default();
+        let arms = macro_arms(&decl);
+        let helpers = derive_helpers(&decl);
+
+        if !arms.is_empty() {
+            arms.iter()
+                .map(|a| format!("{}!{}", name, a))
+                .collect::<Vec<String>>()
+                .join("\n")
+        } else if !helpers.is_empty() {
+            helpers.join("\n")
+        } else {
+            format!("No macro arms found for {}", name)
+        }
+    }
+
+    /// The declaration, deprecation status and inherent methods of the documented item
+    pub fn api_signature(&self) -> ApiSignature {
+        let declaration = match self.tag {
+            locate::Tag::Module | locate::Tag::Keyword => String::new(),
+            _ => self
+                .extract_type_declaration()
+                .replace("[src]", "")
+                .trim()
+                .to_string(),
+        };
+
+        let deprecated = self
+            .contents
+            .find(And(Class("stab"), Class("deprecated")))
+            .any(|n| !has_ancestor_with_class(&n, "impl-items"));
+
+        let methods = self
+            .impl_blocks()
+            .iter()
+            .flat_map(|block| block.find(Class("method")))
+            .filter_map(|n| {
+                let name = n.attr("id")?.strip_prefix("method.")?.to_string();
+                // Only the notes directly under the heading belong to this method
+                let deprecated = n.next().is_some_and(|d| {
+                    (d.is(Class("item-info")) || d.is(Class("stability")))
+                        && d.find(And(Class("stab"), Class("deprecated")))
+                            .next()
+                            .is_some()
+                });
+                Some((name, member_signature(&n), deprecated))
+            })
+            .collect();
+
+        let trait_impls = self
+            .trait_implementations()
+            .into_iter()
+            .map(|header| (implemented_trait(&header), header))
+            .collect();
+
+        let variants = self
+            .contents
+            .find(|n: &Node| n.attr("id").is_some_and(|i| i.starts_with("variant.")))
+            .filter_map(|n| {
+                let name = n.attr("id")?.strip_prefix("variant.")?.to_string();
+                Some((name, n.text().trim().to_string()))
+            })
+            .collect();
+
+        ApiSignature {
+            declaration,
+            deprecated,
+            methods,
+            trait_impls,
+            variants,
+        }
+    }
+
     /// Parse the contents of a located doc file and pretty print them to the terminal
-    pub fn parse_and_print(&self, grep: Option<String>) {
+    pub fn parse_and_print(&self, grep: Option<String>, values: bool) {
+        println!("{}", self.render(grep, values));
+    }
+
+    /// Render the page as `render` does, returning an error for an invalid grep pattern
+    pub fn try_render(&self, grep: Option<String>, values: bool) -> Result<String, Box<dyn Error>> {
+        if let Some(pattern) = &grep {
+            RegexMatcher::new(pattern)?;
+        }
+
+        Ok(self.render(grep, values))
+    }
+
+    /// Parse the contents of a located doc file, formatting them as they would be printed
+    pub fn render(&self, grep: Option<String>, values: bool) -> String {
         let mut sections: Vec<String> = vec![];
+        if let Some(s) = self.use_line() {
+            sections.push(s)
+        }
 
         match self.tag {
             locate::Tag::Module => {
@@ -69,7 +364,12 @@ impl DocParser {
                 if let Some(s) = self.table_with_header("traits", &grep) {
                     sections.push(s)
                 };
-                if let Some(s) = self.table_with_header("constants", &grep) {
+                if let Some(s) =
+                    self.values_table_with_header("constants", "constant", values, &grep)
+                {
+                    sections.push(s)
+                };
+                if let Some(s) = self.values_table_with_header("statics", "static", values, &grep) {
                     sections.push(s)
                 };
                 if let Some(s) = self.table_with_header("structs", &grep) {
@@ -86,18 +386,27 @@ impl DocParser {
                 };
             }
 
-            locate::Tag::Struct => {
+            locate::Tag::Struct | locate::Tag::Primitive => {
                 sections.push(self.extract_type_declaration());
                 if let Some(s) = self.extract_summary() {
                     sections.push(s)
                 }
-                sections.push(self.extract_method_signatures(&grep));
+                if let Some(s) = self.extract_associated_constants(&grep) {
+                    sections.push(s)
+                }
+                sections.extend(self.extract_grouped_methods(&grep));
+                if let Some(s) = self.extract_trait_implementations(&grep) {
+                    sections.push(s)
+                }
             }
 
             locate::Tag::Method => {
                 let s = match self.extract_method() {
                     Some(s) => s,
-                    None => format!("{} is not method", self.method_name.clone().unwrap()),
+                    None => format!(
+                        "{} is not a method or associated constant",
+                        self.method_name.clone().unwrap()
+                    ),
                 };
                 sections.push(s)
             }
@@ -109,6 +418,45 @@ impl DocParser {
                 if let Some(s) = self.extract_enum_variants(&grep) {
                     sections.push(s)
                 };
+                if let Some(s) = self.extract_associated_constants(&grep) {
+                    sections.push(s)
+                }
+                sections.extend(self.extract_grouped_methods(&grep));
+                if let Some(s) = self.extract_trait_implementations(&grep) {
+                    sections.push(s)
+                }
+            }
+
+            locate::Tag::Constant | locate::Tag::Static => {
+                sections.push(const_declaration(&self.extract_type_declaration()));
+                if let Some(s) = self.extract_summary() {
+                    sections.push(s)
+                }
+            }
+
+            locate::Tag::Trait => {
+                if let Some(s) = self.extract_summary() {
+                    sections.push(s)
+                }
+                if let Some(s) = self.extract_associated_constants(&grep) {
+                    sections.push(s)
+                }
+            }
+
+            locate::Tag::Macro => {
+                let decl = self.extract_type_declaration();
+                let helpers = derive_helpers(&decl);
+                if helpers.is_empty() {
+                    sections.push(decl);
+                } else {
+                    sections.push(decl.lines().next().unwrap_or_default().to_string());
+                }
+                if let Some(s) = self.extract_summary() {
+                    sections.push(s)
+                }
+                if let Some(s) = signature_group("helper attributes", helpers, &grep) {
+                    sections.push(s)
+                }
             }
 
             _ => {
@@ -122,67 +470,260 @@ impl DocParser {
         //       This is a quick hack to tidy that up but we should do this in
         //       a smarter way really...
         sections.retain(|s| s.len() > 0);
-        println!("{}", sections.join("\n\n").replace("[src]", ""));
+        sections.join("\n\n").replace("[src]", "")
     }
 
-    fn extract_summary(&self) -> Option<String> {
-        let docblock = self
-            .contents
-            .find(And(Class("docblock"), Not(Class("type-decl"))))
-            .next()?;
-
-        let mut paragraphs: Vec<String> = vec![];
-        for node in docblock.children() {
-            if node.is(Name("p")) {
-                paragraphs.push(node.text());
-            } else if node.text() == "\n" {
-                continue;
-            } else {
-                break;
+    /**
+     * The `use` statement needed to import the located item. Re-exported items are
+     * imported from the shortest public path that we can find, using the canonical
+     * link in the page header along with inlined and listed re-exports in each of the
+     * parent modules.
+     */
+    fn use_line(&self) -> Option<String> {
+        // Primitive types and keywords are always in scope
+        match self.tagged_path.tag {
+            locate::Tag::Primitive | locate::Tag::Keyword => return None,
+            _ if self.tagged_path.file_name.starts_with("primitive.") => return None,
+            _ => (),
+        }
+
+        let module_path = self.tagged_path.module_path();
+        let name = match self.tagged_path.item_name() {
+            Some(name) => name,
+            None if module_path.len() > 1 => {
+                return Some(format!("use {};", module_path.join("::")))
             }
+            None => return None,
+        };
+
+        let mut candidates: Vec<Vec<String>> = vec![];
+        if let Some(p) = self.canonical_path(&module_path[0]) {
+            candidates.push(p);
         }
-        return Some(paragraphs.join("\n\n"));
+
+        let root = self.tagged_path.root()?;
+        for i in 1..module_path.len() {
+            let dir = root.join(module_path[..i].join("/"));
+            let inlined = dir.join(&self.tagged_path.file_name).is_file();
+            let listed = self.is_reexported_from(&root, &dir, &name);
+
+            if inlined || listed {
+                candidates.push(
+                    module_path[..i]
+                        .iter()
+                        .cloned()
+                        .chain(Some(name.clone()))
+                        .collect(),
+                );
+            }
+        }
+
+        candidates.push(module_path.iter().cloned().chain(Some(name)).collect());
+        let shortest = candidates.iter().min_by_key(|c| c.len())?;
+
+        Some(format!("use {};", shortest.join("::")))
+    }
+
+    // Is this item listed in the re-exports of the module in dir under the given name? The
+    // link in the listing has to point back at this item's page: an unrelated item can be
+    // re-exported under the same name.
+    fn is_reexported_from(&self, root: &path::Path, dir: &path::Path, name: &str) -> bool {
+        let index = locate::TaggedPath::with_root(dir.join("index.html"), root);
+        let doc = match load_document(&dir.join("index.html")) {
+            Some(doc) => doc,
+            None => return false,
+        };
+
+        reexport_rows(&doc).iter().any(|(line, hrefs)| {
+            reexported_name(line) == Some(name)
+                && hrefs
+                    .iter()
+                    .filter_map(|href| locate::resolve_link(&index, href))
+                    .any(|linked| linked.path() == self.tagged_path.path())
+        })
+    }
+
+    /**
+     * The module path of the item from the rustdoc canonical link if there is one. Links to
+     * docs.rs include the crate name twice (docs.rs/foo/latest/foo/...) so the path is taken
+     * from the first occurrence of the crate name that gives a page we have locally.
+     */
+    fn canonical_path(&self, crate_name: &str) -> Option<Vec<String>> {
+        let href = self
+            .contents
+            .find(And(Name("link"), Attr("rel", "canonical")))
+            .next()?
+            .attr("href")?;
+        let root = self.tagged_path.root()?;
+
+        let segments: Vec<&str> = href.split('#').next()?.split('/').collect();
+        segments
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| **s == crate_name)
+            .find_map(|(start, _)| {
+                let (file, dirs) = segments[start..].split_last()?;
+                let local = dirs.iter().fold(root.clone(), |p, d| p.join(d)).join(file);
+                if !local.is_file() {
+                    return None;
+                }
+
+                let mut p: Vec<String> = dirs.iter().map(|s| s.to_string()).collect();
+                if *file != "index.html" {
+                    p.push(file.split('.').nth(1)?.to_string());
+                }
+                Some(p)
+            })
+    }
+
+    fn extract_summary(&self) -> Option<String> {
+        document_summary(&self.contents)
     }
 
     // Not Option-al as all structs must have a type declaration
     fn extract_type_declaration(&self) -> String {
-        self.contents
-            .find(Class("type-decl"))
-            .map(|n| n.text())
-            .collect::<Vec<String>>()
-            .join("\n")
+        type_declaration(&self.contents)
     }
 
-    fn extract_method_signatures(&self, grep: &Option<String>) -> String {
-        let impl_block = self.contents.find(Class("impl-items")).next().unwrap();
+    // Inherent impls are all listed before the trait implementations: if we can't
+    // find the list itself then fall back to the first block of impl items.
+    fn impl_blocks(&self) -> Vec<Node<'_>> {
+        match self
+            .contents
+            .find(Attr("id", "implementations-list"))
+            .next()
+        {
+            Some(list) => list.find(Class("impl-items")).collect(),
+            None => self.contents.find(Class("impl-items")).take(1).collect(),
+        }
+    }
+
+    // Newer rustdoc wraps each documented method in a <details> inside the impl items
+    fn method_signatures(&self) -> Vec<String> {
+        self.impl_blocks()
+            .iter()
+            .flat_map(|block| block.find(Class("method")))
+            .map(|n| member_signature(&n))
+            .collect()
+    }
+
+    /**
+     * Split the methods of a type into constructors, other associated functions and
+     * receiver methods in the style of go doc. Free functions in the parent module
+     * that return the type are listed along with the constructors.
+     */
+    fn extract_grouped_methods(&self, grep: &Option<String>) -> Vec<String> {
+        self.grouped_methods()
+            .into_iter()
+            .filter_map(|(heading, sigs)| signature_group(heading, sigs, grep))
+            .collect()
+    }
+
+    fn grouped_methods(&self) -> Vec<(&'static str, Vec<String>)> {
+        let name = self.tagged_path.item_name().unwrap_or_default();
+        let mut constructors: Vec<String> = vec![];
+        let mut associated: Vec<String> = vec![];
         let mut methods: Vec<String> = vec![];
 
-        for node in impl_block.children() {
-            if node.is(Class("method")) {
-                methods.push(node.text());
+        for sig in self.method_signatures() {
+            if has_receiver(&sig) {
+                methods.push(sig);
+            } else if returns_type(&sig, &name) {
+                constructors.push(sig);
             } else {
-                continue;
+                associated.push(sig);
             }
         }
+        constructors.extend(self.module_constructors(&name));
 
-        let raw = methods.join("\n");
-        match grep {
-            Some(grep_str) => matching_lines(raw, grep_str).unwrap(),
-            None => raw,
+        vec![
+            ("constructors", constructors),
+            ("associated functions", associated),
+            ("methods", methods),
+        ]
+    }
+
+    // Free functions listed in the module's functions table whose return type mentions the type.
+    // This runs for every struct and enum lookup so only the function pages that mention the
+    // type at all are parsed.
+    fn module_constructors(&self, name: &str) -> Vec<String> {
+        let dir = path::PathBuf::from(self.tagged_path.dir());
+        let index = match load_document(&dir.join("index.html")) {
+            Some(doc) => doc,
+            None => return vec![],
+        };
+
+        table_rows(&index, "functions")
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| row.first())
+            .filter_map(|f| fs::read_to_string(dir.join(format!("fn.{}.html", f))).ok())
+            .filter(|html| html.contains(name))
+            .map(|html| type_declaration(&Document::from(html.as_str())))
+            .filter(|sig| returns_type(sig, name))
+            .collect()
+    }
+
+    fn trait_implementations(&self) -> Vec<String> {
+        match self
+            .contents
+            .find(Attr("id", "trait-implementations-list"))
+            .next()
+        {
+            Some(list) => list
+                .find(Class("impl"))
+                .map(|n| member_signature(&n))
+                .collect(),
+            None => vec![],
         }
     }
 
+    fn extract_trait_implementations(&self, grep: &Option<String>) -> Option<String> {
+        signature_group("trait implementations", self.trait_implementations(), grep)
+    }
+
+    fn extract_associated_constants(&self, grep: &Option<String>) -> Option<String> {
+        let consts = self
+            .contents
+            .find(|n: &Node| {
+                n.attr("id")
+                    .is_some_and(|i| i.starts_with("associatedconstant."))
+            })
+            .map(|n| const_declaration(&n.text()))
+            .collect::<Vec<String>>();
+
+        signature_group("associated constants", consts, grep)
+    }
+
+    // The heading of a method or associated constant documented on this page
+    fn member_node(&self, name: &str) -> Option<Node<'_>> {
+        self.contents
+            .find(|n: &Node| {
+                n.attr("id").is_some_and(|id| {
+                    MEMBER_ID_PREFIXES
+                        .iter()
+                        .any(|p| id.strip_prefix(p) == Some(name))
+                })
+            })
+            .next()
+    }
+
     fn extract_method(&self) -> Option<String> {
         let mut sections: Vec<String> = vec![];
-        let id = format!("method.{}", self.method_name.clone()?);
-        let node = self
-            .contents
-            .find(|n: &Node| n.attr("id").map_or(false, |i| i == id))
-            .next()?;
+        let node = self.member_node(self.method_name.as_ref()?)?;
 
-        sections.push(node.text());
+        let heading = member_signature(&node);
+        if node.attr("id")?.starts_with("associatedconstant.") {
+            sections.push(const_declaration(&heading));
+        } else {
+            sections.push(heading);
+        }
 
-        if let Some(n) = node.next() {
+        // Newer rustdoc wraps the heading in a <summary> with the docs following it
+        let docs = node
+            .next()
+            .or_else(|| node.parent().filter(|p| p.is(Name("summary")))?.next());
+        if let Some(n) = docs {
             if n.is(Class("docblock")) {
                 // TODO: the raw formatting here isn't great as it becomes one big blob
                 //       probably want to try our own iteration over the children?
@@ -216,26 +757,45 @@ impl DocParser {
     }
 
     fn table_after_header(&self, header: &str) -> Option<String> {
-        Some(
-            pprint::Table::from_rows(
-                self.contents
-                    .find(And(Class("section-header"), |n: &Node| {
-                        n.attr("id").map_or(false, |i| i == header)
-                    }))
-                    .next()? // the header itself
-                    .next()? // a newline...
-                    .next()? // the table
-                    .first_child()? // tbody
-                    .children()
-                    .map(|n| {
-                        n.children()
-                            .map(|c| String::from(c.text().replace("\n", " ").trim_end()))
-                            .collect::<Vec<String>>()
-                    })
-                    .collect::<Vec<Vec<String>>>(),
-            )
-            .as_string(),
-        )
+        Some(pprint::Table::from_rows(table_rows(&self.contents, header)?).as_string())
+    }
+
+    // A module table with an added column holding the declaration of each item
+    fn values_table_with_header(
+        &self,
+        header_str: &str,
+        prefix: &str,
+        values: bool,
+        grep: &Option<String>,
+    ) -> Option<String> {
+        if !values {
+            return self.table_with_header(header_str, grep);
+        }
+
+        let dir = path::PathBuf::from(self.tagged_path.dir());
+        let rows = table_rows(&self.contents, header_str)?
+            .into_iter()
+            .map(|mut row| {
+                let name = row.first().cloned().unwrap_or_default();
+                let decl = load_document(&dir.join(format!("{}.{}.html", prefix, name)))
+                    .map(|doc| const_declaration(&type_declaration(&doc)))
+                    .unwrap_or_default();
+                row.insert(1, decl);
+                row
+            })
+            .collect::<Vec<Vec<String>>>();
+
+        let t = pprint::Table::from_rows(rows).as_string();
+        let s = match grep {
+            Some(grep_str) => matching_lines(t, grep_str).ok()?,
+            None => t,
+        };
+
+        Some(format!(
+            "{}\n{}",
+            header(header_str, SECTION_HEADING_COLOR),
+            s
+        ))
     }
 
     fn table_with_header(&self, header_str: &str, grep: &Option<String>) -> Option<String> {
@@ -252,19 +812,727 @@ impl DocParser {
     }
 }
 
-fn matching_lines(s: String, pattern: &str) -> Result<String, Box<dyn Error>> {
+fn module_node(
+    doc: &Document,
+    dir: &path::Path,
+    name: String,
+    depth: Option<usize>,
+    kinds: &[String],
+) -> ModuleNode {
+    let summary = document_summary(doc)
+        .and_then(|s| s.lines().next().map(String::from))
+        .unwrap_or_default();
+
+    let counts = MODULE_ITEM_SECTIONS
+        .iter()
+        .filter(|(_, kind)| kinds.is_empty() || kinds.iter().any(|k| k == kind))
+        .filter_map(|(section, kind)| {
+            let n = table_rows(doc, section).map_or(0, |rows| rows.len());
+            if n > 0 {
+                Some((*kind, n))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let children = match depth {
+        Some(0) => vec![],
+        _ => table_rows(doc, "modules")
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|row| row.first())
+            .filter_map(|child| {
+                let child_dir = dir.join(child);
+                let child_doc = load_document(&child_dir.join("index.html"))?;
+                let node = module_node(
+                    &child_doc,
+                    &child_dir,
+                    child.clone(),
+                    depth.map(|d| d - 1),
+                    kinds,
+                );
+                if kinds.is_empty() || node.has_items() {
+                    Some(node)
+                } else {
+                    None
+                }
+            })
+            .collect(),
+    };
+
+    ModuleNode {
+        name,
+        summary,
+        counts,
+        children,
+    }
+}
+
+// The signature in the heading of a method or other member of a type. Newer rustdoc puts the
+// version and source link in the heading next to the .code-header holding the signature.
+fn member_signature(node: &Node) -> String {
+    match node.find(Class("code-header")).next() {
+        Some(code) => code.text(),
+        None => node.text().replace("[src]", "").trim().to_string(),
+    }
+}
+
+fn has_ancestor_with_class(node: &Node, class: &str) -> bool {
+    let mut current = node.parent();
+    while let Some(n) = current {
+        if n.is(Class(class)) {
+            return true;
+        }
+        current = n.parent();
+    }
+    false
+}
+
+// Links to the rendered source of an item: "srclink" in older rustdoc and "src" in newer
+fn is_source_link(node: &Node) -> bool {
+    node.name() == Some("a")
+        && node
+            .attr("class")
+            .is_some_and(|c| c.split_whitespace().any(|c| c == "srclink" || c == "src"))
+}
+
+// Collect the text of a rendered source page, skipping the line number links that newer
+// versions of rustdoc interleave with the code
+/// The code on a source page rendered by rustdoc, without its line numbers
+pub(crate) fn rendered_source_code(page: &path::Path) -> Option<String> {
+    let doc = load_document(page)?;
+    let pre = doc.find(And(Name("pre"), Class("rust"))).next()?;
+    let mut code = String::new();
+    rendered_code(&pre, &mut code);
+
+    Some(code)
+}
+
+fn rendered_code(node: &Node, buf: &mut String) {
+    for child in node.children() {
+        if child.attr("data-nosnippet").is_some() {
+            continue;
+        }
+        match child.as_text() {
+            Some(text) => buf.push_str(text),
+            None => rendered_code(&child, buf),
+        }
+    }
+}
+
+fn load_document(path: &path::Path) -> Option<Document> {
+    let file = fs::File::open(path).ok()?;
+    Document::from_read(file).ok()
+}
+
+/// The first line of the summary of a crate (or module) from its index page
+pub(crate) fn crate_summary(index: &path::Path) -> Option<String> {
+    let doc = load_document(index)?;
+    document_summary(&doc).and_then(|s| s.lines().next().map(String::from))
+}
+
+fn document_summary(doc: &Document) -> Option<String> {
+    let docblock = doc
+        .find(And(Class("docblock"), Not(Class("type-decl"))))
+        .next()?;
+
+    let mut paragraphs: Vec<String> = vec![];
+    for node in docblock.children() {
+        if node.is(Name("p")) {
+            paragraphs.push(node.text());
+        } else if node.text() == "\n" {
+            continue;
+        } else {
+            break;
+        }
+    }
+    Some(paragraphs.join("\n\n"))
+}
+
+// The declaration block of an item page: a "type-decl" docblock in older rustdoc and a
+// pre.item-decl in newer
+fn type_declaration(doc: &Document) -> String {
+    doc.find(Or(Class("type-decl"), Class("item-decl")))
+        .map(|n| n.text())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// The cells of each row of the item list following the given section header. Older rustdoc
+// uses a table with a row per item, newer a <dl> with a <dt> name and <dd> summary per item.
+fn section_rows<'a>(doc: &'a Document, header: &str) -> Option<Vec<Vec<Node<'a>>>> {
+    let mut list = doc
+        .find(And(Class("section-header"), |n: &Node| {
+            n.attr("id") == Some(header)
+        }))
+        .next()? // the header itself
+        .next()?;
+    // skip the newline between the header and the list
+    while list.as_text().is_some() {
+        list = list.next()?;
+    }
+
+    if !list.is(Name("dl")) {
+        return Some(
+            list.first_child()? // tbody
+                .children()
+                .map(|row| row.children().collect())
+                .collect(),
+        );
+    }
+
+    let mut rows: Vec<Vec<Node>> = vec![];
+    for item in list.children() {
+        match item.name() {
+            Some("dt") => rows.push(vec![item]),
+            Some("dd") => {
+                if let Some(row) = rows.last_mut() {
+                    row.push(item);
+                }
+            }
+            _ => (),
+        }
+    }
+    Some(rows)
+}
+
+// The text content of each cell in the table following the given section header. Stability
+// labels ("Deprecated", "Experimental") are left out so the first cell is always the name.
+fn table_rows(doc: &Document, header: &str) -> Option<Vec<Vec<String>>> {
+    Some(
+        section_rows(doc, header)?
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        let text: String = cell
+                            .children()
+                            .filter(|c| !c.is(Class("stab")))
+                            .map(|c| c.text())
+                            .collect();
+                        String::from(text.replace("\n", " ").trim_end())
+                    })
+                    .collect::<Vec<String>>()
+            })
+            .collect::<Vec<Vec<String>>>(),
+    )
+}
+
+// Each re-export listed on a module page along with the links in its `pub use` line
+fn reexport_rows(doc: &Document) -> Vec<(String, Vec<String>)> {
+    section_rows(doc, "reexports")
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|row| {
+            let line = row.first()?;
+            let hrefs = line
+                .find(Name("a"))
+                .filter_map(|a| a.attr("href").map(String::from))
+                .collect();
+            Some((line.text().replace("\n", " ").trim_end().to_string(), hrefs))
+        })
+        .collect()
+}
+
+// A const or static declaration as `const NAME: Type = value;` without its visibility. Newer
+// rustdoc follows the declaration with a comment holding the evaluated value, which is kept.
+fn const_declaration(decl: &str) -> String {
+    let decl = decl.replace("[src]", "");
+    let (decl, comment) = match decl.split_once("; //") {
+        Some((decl, comment)) => (decl, Some(comment.trim())),
+        None => (decl.as_str(), None),
+    };
+    let mut s = decl.trim().to_string();
+    if let Some(vis) = s.strip_prefix("pub") {
+        if vis.starts_with(' ') || vis.starts_with('(') {
+            let rest = match vis.trim_start().strip_prefix('(') {
+                Some(inner) => inner.split_once(')').map_or("", |(_, r)| r),
+                None => vis,
+            };
+            s = rest.trim().to_string();
+        }
+    }
+
+    if !s.is_empty() && !s.ends_with(';') {
+        s.push(';');
+    }
+    if let Some(comment) = comment {
+        s = format!("{} // {}", s, comment);
+    }
+    s
+}
+
+// The matcher of each arm in a macro_rules! declaration block
+fn macro_arms(decl: &str) -> Vec<String> {
+    if !decl.trim_start().starts_with("macro_rules!") {
+        return vec![];
+    }
+
+    decl.lines()
+        .filter_map(|line| line.rsplit_once("=>"))
+        .map(|(pattern, _)| pattern.trim().to_string())
+        .filter(|pattern| !pattern.is_empty())
+        .collect()
+}
+
+// The helper attributes listed in the declaration block of a derive macro
+fn derive_helpers(decl: &str) -> Vec<String> {
+    let mut lines = decl.lines().map(str::trim);
+    match lines.next() {
+        Some(first) if first.starts_with("#[derive(") => lines
+            .filter(|line| line.starts_with("#["))
+            .map(String::from)
+            .collect(),
+        _ => vec![],
+    }
+}
+
+// The trait being implemented in an impl header such as `impl<T: Debug> Debug for Foo<T>`
+fn implemented_trait(impl_header: &str) -> String {
+    let mut s = impl_header
+        .trim()
+        .trim_start_matches("unsafe ")
+        .trim_start_matches("impl");
+    if s.starts_with('<') {
+        let mut depth = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                _ => (),
+            }
+            if depth == 0 {
+                s = &s[i + 1..];
+                break;
+            }
+        }
+    }
+
+    match s.rsplit_once(" for ") {
+        Some((t, _)) => t.trim().to_string(),
+        None => s.trim().to_string(),
+    }
+}
+
+// The name bound by a `pub use` line from a re-exports table (glob imports bind nothing)
+fn reexported_name(line: &str) -> Option<&str> {
+    let target = line
+        .trim()
+        .trim_start_matches("pub use ")
+        .trim_end_matches(';')
+        .trim();
+
+    let name = match target.rsplit_once(" as ") {
+        Some((_, alias)) => alias.trim(),
+        None => target.rsplit("::").next()?,
+    };
+
+    if name == "*" || name.ends_with('}') {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+fn signature_group(heading: &str, sigs: Vec<String>, grep: &Option<String>) -> Option<String> {
+    if sigs.is_empty() {
+        return None;
+    }
+
+    let raw = sigs.join("\n");
+    let s = match grep {
+        Some(grep_str) => matching_lines(raw, grep_str).ok()?,
+        None => raw,
+    };
+
+    if s.is_empty() {
+        None
+    } else {
+        Some(format!("{}\n{}", header(heading, SECTION_HEADING_COLOR), s))
+    }
+}
+
+// Split a rendered fn signature into its argument list and (optional) return type
+fn split_signature(sig: &str) -> Option<(&str, Option<&str>)> {
+    let mut angle_depth = 0;
+    let mut start = None;
+    for (i, c) in sig.char_indices() {
+        match c {
+            '<' => angle_depth += 1,
+            '>' if angle_depth > 0 && !sig[..i].ends_with('-') => angle_depth -= 1,
+            '(' if angle_depth == 0 => {
+                start = Some(i);
+                break;
+            }
+            _ => (),
+        }
+    }
+
+    let start = start?;
+    let mut paren_depth = 0;
+    for (i, c) in sig[start..].char_indices() {
+        match c {
+            '(' => paren_depth += 1,
+            ')' => {
+                paren_depth -= 1;
+                if paren_depth == 0 {
+                    let args = &sig[start + 1..start + i];
+                    let rest = sig[start + i + 1..].trim_start();
+                    let ret = rest.strip_prefix("->").map(|r| match r.find("where") {
+                        Some(ix) => r[..ix].trim(),
+                        None => r.trim(),
+                    });
+                    return Some((args, ret));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+// Does this signature take self, &self, &mut self or self: T as its first argument?
+fn has_receiver(sig: &str) -> bool {
+    let args = match split_signature(sig) {
+        Some((args, _)) => args,
+        None => return false,
+    };
+
+    let mut first = args.split(',').next().unwrap_or("").trim();
+    first = first.trim_start_matches("mut ").trim_start_matches('&');
+    if first.starts_with('\'') {
+        first = first.split_once(' ').map_or("", |(_, rest)| rest);
+    }
+    first = first.trim_start_matches("mut ");
+
+    first == "self" || first.starts_with("self:") || first.starts_with("self ")
+}
+
+// Does the return type of this signature mention Self or the named type?
+fn returns_type(sig: &str, name: &str) -> bool {
+    match split_signature(sig) {
+        Some((_, Some(ret))) => ret
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|word| word == "Self" || (!name.is_empty() && word == name)),
+        _ => false,
+    }
+}
+
+pub(crate) fn matching_lines(s: String, pattern: &str) -> Result<String, Box<dyn Error>> {
     let matcher = RegexMatcher::new(pattern)?;
     let mut matches: Vec<String> = vec![];
-    let lines: Vec<&str> = s.split('\n').collect();
 
     Searcher::new().search_slice(
         &matcher,
         s.as_bytes(),
-        UTF8(|lnum, _| {
-            matches.push(lines[lnum as usize].to_string());
+        UTF8(|_, line| {
+            matches.push(line.trim_end_matches('\n').to_string());
             Ok(true)
         }),
     )?;
 
     Ok(matches.join("\n"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("pub fn new(query: String) -> Self", false, true)]
+    #[test_case("pub fn len(&self) -> usize", true, false)]
+    #[test_case("pub fn push(&mut self, value: T)", true, false)]
+    #[test_case("pub fn into_inner(self) -> Locator", true, true)]
+    #[test_case("pub fn get<'a>(&'a self) -> &'a str", true, false)]
+    #[test_case("pub fn with_capacity(capacity: usize) -> Locator", false, true)]
+    #[test_case(
+        "pub fn from_fn<F: Fn(usize) -> u8>(f: F) -> Option<Locator>",
+        false,
+        true
+    )]
+    #[test_case("pub fn default_root() -> PathBuf", false, false)]
+    fn signatures_are_classified(sig: &str, receiver: bool, returns: bool) {
+        assert_eq!(has_receiver(sig), receiver);
+        assert_eq!(returns_type(sig, "Locator"), returns);
+    }
+
+    #[test_case("test_resources/methods/krate/struct.Widget.html", "fn"; "older rustdoc")]
+    #[test_case("test_resources/current/krate/struct.Widget.html", "const fn"; "current rustdoc")]
+    fn methods_are_grouped_with_module_constructors(page: &str, new_fn: &str) {
+        let tagged_path = locate::TaggedPath::from(path::PathBuf::from(page));
+
+        assert_eq!(
+            DocParser::new(tagged_path).grouped_methods(),
+            vec![
+                (
+                    "constructors",
+                    vec![
+                        format!("pub {} new(name: &str) -> Self", new_fn),
+                        "pub fn make<'a>(parts: &[&'a str]) -> Option<Widget>".to_string(),
+                    ]
+                ),
+                (
+                    "associated functions",
+                    vec!["pub fn count() -> usize".to_string()]
+                ),
+                ("methods", vec!["pub fn len(&self) -> usize".to_string()]),
+            ]
+        );
+    }
+
+    #[test_case(
+        "pub const PI: f64 = 3.14159265358979323846264338327950288f64;",
+        "const PI: f64 = 3.14159265358979323846264338327950288f64;"
+    )]
+    #[test_case("pub(crate) static COUNT: AtomicUsize", "static COUNT: AtomicUsize;")]
+    #[test_case(
+        "pub const E: f64 = 2.718_f64; // 2.718f64",
+        "const E: f64 = 2.718_f64; // 2.718f64"
+    )]
+    #[test_case(
+        "const MAX: u32 = 4_294_967_295u32[src]",
+        "const MAX: u32 = 4_294_967_295u32;"
+    )]
+    fn const_declarations(decl: &str, expected: &str) {
+        assert_eq!(const_declaration(decl), expected);
+    }
+
+    #[test]
+    fn macro_rules_arms_are_extracted() {
+        let decl = "macro_rules! vec {\n    () => { ... };\n    ($elem:expr; $n:expr) => { ... };\n    ($($x:expr),+ $(,)?) => { ... };\n}";
+
+        assert_eq!(
+            macro_arms(decl),
+            vec!["()", "($elem:expr; $n:expr)", "($($x:expr),+ $(,)?)"]
+        );
+        assert!(derive_helpers(decl).is_empty());
+    }
+
+    #[test_case("macro.println.html", "println!()\nprintln!($($arg:tt)*)")]
+    #[test_case(
+        "macro.matches.html",
+        "matches!($expression:expr, $pattern:pat $(if $guard:expr)? $(,)?)"
+    )]
+    fn macro_arms_are_read_from_current_rustdoc(page: &str, expected: &str) {
+        let root = path::Path::new("test_resources/current");
+        let tagged_path = locate::TaggedPath::with_root(root.join("std").join(page), root);
+        let parser = DocParser::new(tagged_path);
+
+        assert_eq!(parser.macro_arm_lines(), expected);
+        assert!(parser.render(None, false).contains("macro_rules! "));
+    }
+
+    #[test]
+    fn derive_helpers_are_extracted() {
+        let decl =
+            "#[derive(Serialize)]\n{\n    // Attributes available to this derive:\n    #[serde]\n}";
+
+        assert_eq!(derive_helpers(decl), vec!["#[serde]"]);
+        assert!(macro_arms(decl).is_empty());
+    }
+
+    #[test_case("tree/foo/index.html", "functions", &["one", "two"]; "table")]
+    #[test_case("current/std/index.html", "keywords", &["as", "fn", "SelfTy"]; "definition list")]
+    #[test_case("current/std/index.html", "modules", &["collections", "i8"]; "stability labels")]
+    fn item_tables_are_read(page: &str, section: &str, expected: &[&str]) {
+        let doc = load_document(&path::Path::new("test_resources").join(page)).unwrap();
+        let rows = table_rows(&doc, section).unwrap();
+        let names: Vec<&str> = rows.iter().map(|r| r[0].as_str()).collect();
+
+        assert_eq!(names, expected);
+        assert!(rows.iter().all(|r| r.len() == 2 && !r[1].is_empty()));
+    }
+
+    #[test_case(None, &[], vec![("foo", "[2 fn]"), ("  bar", "[1 struct]")])]
+    #[test_case(Some(0), &[], vec![("foo", "[2 fn]")])]
+    #[test_case(None, &["struct"], vec![("foo", "[]"), ("  bar", "[1 struct]")])]
+    fn module_tree_rows(depth: Option<usize>, kinds: &[&str], expected: Vec<(&str, &str)>) {
+        let dir = path::Path::new("test_resources/tree/foo");
+        let doc = load_document(&dir.join("index.html")).unwrap();
+        let kinds: Vec<String> = kinds.iter().map(|k| k.to_string()).collect();
+        let tree = module_node(&doc, dir, "foo".into(), depth, &kinds);
+
+        let mut rows = vec![];
+        tree.add_rows(0, &mut rows);
+        let summary: Vec<(&str, &str)> = rows
+            .iter()
+            .map(|r| (r[0].as_ref(), r[1].as_ref()))
+            .collect();
+
+        assert_eq!(summary, expected);
+        assert_eq!(rows[0][2], "The foo crate.");
+    }
+
+    #[test]
+    fn module_trees_are_read_from_current_rustdoc() {
+        let dir = path::Path::new("test_resources/current/std/collections");
+        let doc = load_document(&dir.join("index.html")).unwrap();
+        let tree = module_node(&doc, dir, "collections".into(), Some(1), &[]);
+
+        let mut rows = vec![];
+        tree.add_rows(0, &mut rows);
+        assert_eq!(
+            rows,
+            vec![
+                vec!["collections", "[2 struct, 1 enum]", "Collection types."],
+                vec![
+                    "  hash_map",
+                    "[2 struct, 1 enum]",
+                    "A hash map implemented with quadratic probing and SIMD lookup."
+                ],
+            ]
+        );
+    }
+
+    // Searcher line numbers start from 1: the first and last lines must both be found
+    #[test_case("first", "first line")]
+    #[test_case("last", "last line")]
+    #[test_case("line", "first line\nmiddle line\nlast line")]
+    fn matching_lines_are_found(pattern: &str, expected: &str) {
+        let text = String::from("first line\nmiddle line\nlast line");
+        assert_eq!(matching_lines(text, pattern).unwrap(), expected);
+    }
+
+    #[test_case("impl Debug for Locator", "Debug")]
+    #[test_case("impl<T: Clone> From<Vec<T>> for Thing<T>", "From<Vec<T>>")]
+    #[test_case("unsafe impl<T: Send> Send for Thing<T>", "Send")]
+    #[test_case("impl<T> !Sync for Thing<T>", "!Sync")]
+    fn implemented_traits(header: &str, expected: &str) {
+        assert_eq!(implemented_trait(header), expected);
+    }
+
+    #[test_case("pub use self::hash_map::HashMap;", Some("HashMap"))]
+    #[test_case("pub use core::option::Option as Maybe;", Some("Maybe"))]
+    #[test_case("pub use self::prelude::*;", None)]
+    fn reexported_names(line: &str, expected: Option<&str>) {
+        assert_eq!(reexported_name(line), expected);
+    }
+
+    #[test_case("inner/struct.Thing.html", "use krate::inner::Thing;"; "docs.rs canonical link")]
+    #[test_case("inner/struct.Widget.html", "use krate::Widget;"; "reexported")]
+    #[test_case("other/struct.Thing.html", "use krate::Thing;"; "reexported by name")]
+    fn use_lines(file: &str, expected: &str) {
+        let root = path::Path::new("test_resources/uses");
+        let tagged_path = locate::TaggedPath::with_root(root.join("krate").join(file), root);
+
+        assert_eq!(
+            DocParser::new(tagged_path).use_line(),
+            Some(expected.to_string())
+        );
+    }
+
+    #[test]
+    fn canonical_paths_skip_the_docs_rs_prefix() {
+        let root = path::Path::new("test_resources/uses");
+        let tagged_path =
+            locate::TaggedPath::with_root(root.join("krate/inner/struct.Thing.html"), root);
+
+        assert_eq!(
+            DocParser::new(tagged_path).canonical_path("krate"),
+            Some(vec!["krate".into(), "inner".into(), "Thing".into()])
+        );
+    }
+
+    #[test_case(None, "../src/krate/point.rs.html#3-6"; "item")]
+    #[test_case(Some("origin"), "../src/krate/point.rs.html#9-11"; "method with link in heading")]
+    #[test_case(Some("norm"), "../src/krate/point.rs.html#13-15"; "method with link beside heading")]
+    fn source_links_are_found(method: Option<&str>, expected: &str) {
+        let mut tagged_path = locate::TaggedPath::from(path::PathBuf::from(
+            "test_resources/source/struct.Point.html",
+        ));
+        tagged_path.method_name = method.map(String::from);
+
+        assert_eq!(
+            DocParser::new(tagged_path).source_link(),
+            Some(expected.to_string())
+        );
+    }
+
+    #[test_case("MAX", "const MAX: u32;\n\nThe largest value that can be represented by this integer type."; "associated constant")]
+    #[test_case("count_ones", "pub const fn count_ones(self) -> u32\n\nReturns the number of ones."; "method")]
+    fn primitive_members_are_found(name: &str, expected: &str) {
+        let mut tagged_path = locate::TaggedPath::from(path::PathBuf::from(
+            "test_resources/primitive/primitive.u32.html",
+        ));
+        tagged_path.tag = locate::Tag::Method;
+        tagged_path.method_name = Some(name.to_string());
+
+        assert_eq!(
+            DocParser::new(tagged_path).extract_method(),
+            Some(expected.to_string())
+        );
+    }
+
+    #[test]
+    fn constants_are_read_from_current_rustdoc() {
+        let root = path::Path::new("test_resources/current");
+        let dir = root.join("std/f64/consts");
+        let pi = DocParser::new(locate::TaggedPath::with_root(
+            dir.join("constant.PI.html"),
+            root,
+        ));
+        let consts = DocParser::new(locate::TaggedPath::with_root(dir.join("index.html"), root));
+        let decl =
+            "const PI: f64 = 3.14159265358979323846264338327950288_f64; // 3.1415926535897931f64";
+
+        assert_eq!(
+            pi.render(None, false),
+            format!(
+                "use std::f64::consts::PI;\n\n{}\n\nArchimedes’ constant (π)",
+                decl
+            )
+        );
+        let values = consts
+            .values_table_with_header("constants", "constant", true, &None)
+            .unwrap();
+        assert!(values.contains(decl));
+        assert!(values.contains("const E: f64 = "));
+    }
+
+    #[test]
+    fn links_are_found() {
+        let tagged_path =
+            locate::TaggedPath::from(path::PathBuf::from("test_resources/tree/foo/index.html"));
+
+        assert_eq!(
+            DocParser::new(tagged_path).links(),
+            vec![
+                "#modules",
+                "bar/index.html",
+                "#functions",
+                "fn.one.html",
+                "fn.two.html"
+            ]
+        );
+    }
+
+    #[test]
+    fn method_names_are_found() {
+        let tagged_path = locate::TaggedPath::from(path::PathBuf::from(
+            "test_resources/source/struct.Point.html",
+        ));
+
+        assert_eq!(
+            DocParser::new(tagged_path).method_names(),
+            vec!["norm", "origin"]
+        );
+    }
+
+    #[test_case(None, "pub struct Point {\n    pub x: f64,\n    pub y: f64,\n}"; "item")]
+    #[test_case(Some("norm"), "pub fn norm(&self) -> f64 {\n    (self.x * self.x + self.y * self.y).sqrt()\n}"; "method")]
+    fn item_source_is_read_from_rendered_pages(method: Option<&str>, expected: &str) {
+        let mut tagged_path = locate::TaggedPath::from(path::PathBuf::from(
+            "test_resources/source/struct.Point.html",
+        ));
+        tagged_path.method_name = method.map(String::from);
+
+        let (file, code) = DocParser::new(tagged_path).item_source().unwrap();
+        assert_eq!(
+            file,
+            path::PathBuf::from("test_resources/src/krate/point.rs.html")
+        );
+        assert_eq!(code, expected);
+    }
+}
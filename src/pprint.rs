@@ -9,6 +9,7 @@ use term_size;
 pub(crate) const CRATE_LIST_HEADING_COLOR: &'static str = "blue";
 pub(crate) const SECTION_HEADING_COLOR: &'static str = "yellow";
 pub(crate) const ENUM_HEADING_COLOR: &'static str = "green";
+pub(crate) const SOURCE_HEADING_COLOR: &str = "magenta";
 
 // Space between columns when pretty printing
 const SPACER: &'static str = "  ";
@@ -39,7 +40,7 @@ pub(crate) fn pprint_as_columns(elems: Vec<String>) -> String {
         .join("\n")
 }
 
-fn max_width() -> usize {
+pub(crate) fn max_width() -> usize {
     if let Some((w, _)) = term_size::dimensions() {
         w
     } else {
@@ -62,25 +63,28 @@ impl Row {
             .join(SPACER)
     }
 
-    fn two_column_wrapped(&self, column_widths: &Vec<usize>) -> String {
-        if self.cells.len() != 2 {
-            panic!(
-                "two_column_wrapped called on row with {} columns",
-                self.cells.len()
-            );
+    fn last_column_wrapped(&self, column_widths: &Vec<usize>) -> String {
+        if self.cells.len() < 2 {
+            return self.formatted(column_widths);
         }
 
         let max = max_width();
-        if column_widths.iter().sum::<usize>() + SPACER.len() <= max {
-            return self.formatted(&column_widths);
+        let n = self.cells.len();
+        if column_widths.iter().take(n).sum::<usize>() + SPACER.len() * (n - 1) <= max {
+            return self.formatted(column_widths);
         }
 
         let mut buf = String::new();
         let mut current = String::new();
-        let offset = vec![" "; column_widths[0] + SPACER.len() + 1].join("");
-        current.push_str(&(format!("{:01$}", self.cells[0], column_widths[0]) + SPACER));
+        let prefix = Row {
+            cells: self.cells[..n - 1].to_vec(),
+        }
+        .formatted(column_widths)
+            + SPACER;
+        let offset = vec![" "; prefix.len() + 1].join("");
+        current.push_str(&prefix);
 
-        for word in self.cells[1].split_whitespace() {
+        for word in self.cells[n - 1].split_whitespace() {
             if current.len() + word.len() + 1 < max {
                 current.push_str(&format!(" {}", word));
             } else {
@@ -140,8 +144,65 @@ impl Table {
     pub fn as_string(&self) -> String {
         self.rows
             .iter()
-            .map(|r| r.two_column_wrapped(&self.column_widths))
+            .map(|r| r.last_column_wrapped(&self.column_widths))
             .collect::<Vec<String>>()
             .join("\n")
     }
 }
+
+/**
+ * Render a Markdown document (such as a rustc error code explanation) using the
+ * same plain styling that we use for rustdoc docblocks: headings become section
+ * headers, code blocks are indented and lines hidden from rustdoc are dropped.
+ */
+pub(crate) fn render_markdown(md: &str) -> String {
+    let mut lines: Vec<String> = vec![];
+    let mut in_code = false;
+
+    for line in md.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+
+        if in_code {
+            if !is_hidden_code_line(line) {
+                lines.push(format!("    {}", line));
+            }
+        } else if line.starts_with('#') {
+            let heading = line.trim_start_matches('#').trim();
+            lines.push(header(heading, SECTION_HEADING_COLOR));
+        } else {
+            lines.push(line.replace('`', ""));
+        }
+    }
+
+    lines.join("\n")
+}
+
+/// The contents of each fenced code block in a Markdown document
+pub(crate) fn markdown_code_blocks(md: &str) -> Vec<String> {
+    let mut blocks: Vec<String> = vec![];
+    let mut current: Option<Vec<&str>> = None;
+
+    for line in md.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(block) => blocks.push(block.join("\n")),
+                None => current = Some(vec![]),
+            }
+        } else if let Some(block) = current.as_mut() {
+            if !is_hidden_code_line(line) {
+                block.push(line);
+            }
+        }
+    }
+
+    blocks
+}
+
+// Lines starting with '# ' are hidden in rendered rustdoc examples
+fn is_hidden_code_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed == "#" || trimmed.starts_with("# ")
+}
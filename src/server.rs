@@ -0,0 +1,526 @@
+/*!
+ * A long running server for editor plugins
+ *
+ * Requests and responses are JSON-RPC 2.0 messages, one per line. An index of every documented
+ * item and recently parsed doc pages are kept in memory between requests so that we don't pay
+ * for walking the doc directories and parsing large pages each time. Cached state is dropped
+ * whenever the documentation on disk is rebuilt.
+ *
+ * Supported methods are:
+ *   resolve  {query} or {file, line, column} -> the located item or null
+ *   search   {query, limit?}                  -> items whose path contains the query
+ *   complete {partial}                        -> completions for a partial query path
+ *   render   {query, grep?, values?}          -> the docs as they would be printed, or null
+ *   shutdown                                  -> stops the server
+ */
+use crate::{
+    complete, cursor,
+    locate::{self, Locator, TaggedPath},
+    parse::DocParser,
+    source,
+};
+use select::document::Document;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, BufRead, Write},
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::SystemTime,
+};
+
+// Error codes defined by the JSON-RPC spec
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+// The number of parsed doc pages that we keep before starting again
+const MAX_CACHED_DOCUMENTS: usize = 256;
+
+// The number of search results returned if the request doesn't specify a limit
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+
+type RpcResult = Result<Value, (i64, String)>;
+
+// The items documented under a doc root, along with the stamp of the root when indexed
+struct RootIndex {
+    stamp: Option<SystemTime>,
+    entries: Vec<(String, TaggedPath)>,
+}
+
+/// The cached state that is shared between requests
+#[derive(Default)]
+pub struct Server {
+    // The doc roots given up front, or None to look for the local doc roots on each refresh
+    fixed_roots: Option<Vec<PathBuf>>,
+    roots: Vec<PathBuf>,
+    indices: HashMap<PathBuf, RootIndex>,
+    documents: HashMap<PathBuf, (SystemTime, Rc<Document>)>,
+    shutdown: bool,
+}
+
+impl Server {
+    /// A server for the doc roots of the selected toolchain and the current crate. The roots
+    /// are looked up again before each request so that docs built later on are picked up.
+    pub fn new() -> Self {
+        Server::default()
+    }
+
+    /// A server that indexes the given doc roots for search and completion requests
+    pub fn with_roots(roots: Vec<PathBuf>) -> Self {
+        Server {
+            fixed_roots: Some(roots),
+            ..Default::default()
+        }
+    }
+
+    /**
+     * Handle a single line of input, returning the response to send back. Notifications
+     * (requests without an id) are handled but do not get a response.
+     */
+    pub fn handle(&mut self, line: &str) -> Option<Value> {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(req) => req,
+            Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+        };
+        let id = request.get("id").cloned();
+        let method = match request.get("method").and_then(Value::as_str) {
+            Some(m) => m,
+            None => {
+                let message = String::from("missing method");
+                return Some(error_response(
+                    id.unwrap_or_default(),
+                    INVALID_REQUEST,
+                    message,
+                ));
+            }
+        };
+        let params = request.get("params").cloned().unwrap_or_default();
+
+        let res = self.dispatch(method, &params);
+
+        let id = id?;
+        Some(match res {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => error_response(id, code, message),
+        })
+    }
+
+    fn dispatch(&mut self, method: &str, params: &Value) -> RpcResult {
+        match method {
+            "resolve" => self.resolve(params),
+            "search" => self.search(params),
+            "complete" => self.complete(params),
+            "render" => self.render(params),
+            "shutdown" => {
+                self.shutdown = true;
+                Ok(Value::Null)
+            }
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method: {}", method))),
+        }
+    }
+
+    fn resolve(&mut self, params: &Value) -> RpcResult {
+        let query = query_param(params)?;
+        let tagged_path = match self.tagged_path(&query) {
+            Some(p) => p,
+            None => {
+                return Ok(match source::source_location(&query) {
+                    Some(loc) => json!({
+                        "query": query,
+                        "item": query,
+                        "kind": Value::Null,
+                        "page": Value::Null,
+                        "source": loc.to_string(),
+                    }),
+                    None => Value::Null,
+                })
+            }
+        };
+
+        let item = match &tagged_path.method_name {
+            Some(m) => format!("{}::{}", tagged_path.item_path(), m),
+            None => tagged_path.item_path(),
+        };
+        let page = tagged_path.path();
+        let kind = tagged_path.tag.to_string();
+        let source = self
+            .parser(tagged_path)
+            .and_then(|p| p.source_location())
+            .map(|loc| loc.to_string());
+
+        Ok(json!({
+            "query": query,
+            "item": item,
+            "kind": kind,
+            "page": page,
+            "source": source,
+        }))
+    }
+
+    fn search(&mut self, params: &Value) -> RpcResult {
+        let query = string_param(params, "query")?.to_lowercase();
+        let limit = params
+            .get("limit")
+            .and_then(Value::as_u64)
+            .map_or(DEFAULT_SEARCH_LIMIT, |n| n as usize);
+
+        self.refresh_indices();
+        let mut matches: Vec<(usize, &String, &TaggedPath)> = self
+            .entries()
+            .filter_map(|(path, tagged)| {
+                let lower = path.to_lowercase();
+                let name = lower.rsplit("::").next().unwrap_or_default();
+                let rank = if name == query {
+                    0
+                } else if name.starts_with(&query) {
+                    1
+                } else if name.contains(&query) {
+                    2
+                } else if lower.contains(&query) {
+                    3
+                } else {
+                    return None;
+                };
+                Some((rank, path, tagged))
+            })
+            .collect();
+        matches.sort_by_key(|(rank, path, _)| (*rank, path.len(), path.as_str()));
+
+        let results: Vec<Value> = matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, path, tagged)| {
+                json!({ "item": path, "kind": tagged.tag.to_string(), "page": tagged.path() })
+            })
+            .collect();
+
+        Ok(json!(results))
+    }
+
+    fn complete(&mut self, params: &Value) -> RpcResult {
+        let partial = string_param(params, "partial")?;
+        self.refresh_roots();
+
+        Ok(json!(complete::complete_in(&self.roots, partial)))
+    }
+
+    fn render(&mut self, params: &Value) -> RpcResult {
+        let query = query_param(params)?;
+        let grep = params.get("grep").and_then(Value::as_str).map(String::from);
+        let values = params
+            .get("values")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let rendered = match self.tagged_path(&query) {
+            Some(tagged_path) => match self.parser(tagged_path) {
+                Some(parser) => Some(
+                    parser
+                        .try_render(grep, values)
+                        .map_err(|e| (INVALID_PARAMS, e.to_string()))?,
+                ),
+                None => None,
+            },
+            None => source::render_from_source(&query, grep),
+        };
+
+        Ok(json!(rendered))
+    }
+
+    // Look for the doc roots again, dropping the indices of any that have gone away
+    fn refresh_roots(&mut self) {
+        self.roots = match &self.fixed_roots {
+            Some(roots) => roots.clone(),
+            None => locate::doc_roots(),
+        };
+        let roots = &self.roots;
+        self.indices.retain(|root, _| roots.contains(root));
+    }
+
+    // Re-index any doc roots that have changed since we last looked at them
+    fn refresh_indices(&mut self) {
+        self.refresh_roots();
+        for root in self.roots.iter() {
+            let stamp = root_stamp(root);
+            if self
+                .indices
+                .get(root)
+                .is_some_and(|index| index.stamp == stamp)
+            {
+                continue;
+            }
+
+            let mut entries: Vec<(String, TaggedPath)> = locate::walk_tagged_paths(root, &[])
+                .unwrap_or_default()
+                .into_iter()
+                .map(|tagged| (tagged.item_path(), tagged))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+            self.documents.retain(|path, _| !path.starts_with(root));
+            self.indices
+                .insert(root.clone(), RootIndex { stamp, entries });
+        }
+    }
+
+    // Every indexed item, in the order of the doc roots
+    fn entries(&self) -> impl Iterator<Item = &(String, TaggedPath)> {
+        self.roots
+            .iter()
+            .filter_map(move |r| self.indices.get(r))
+            .flat_map(|index| index.entries.iter())
+    }
+
+    // The page for a query. Items are looked up in the index first so that only paths that
+    // aren't indexed (methods, keywords, primitives...) need the Locator to walk the docs.
+    fn tagged_path(&mut self, query: &str) -> Option<TaggedPath> {
+        self.refresh_indices();
+        let mut indexed = self
+            .roots
+            .iter()
+            .filter_map(|r| self.indices.get(r))
+            .flat_map(|index| {
+                let start = index
+                    .entries
+                    .partition_point(|(path, _)| path.as_str() < query);
+                index.entries[start..]
+                    .iter()
+                    .take_while(|(path, _)| path == query)
+            });
+
+        // Paths shared by more than one page (e.g. a module and a primitive) are left to the
+        // Locator to decide between
+        match (indexed.next(), indexed.next()) {
+            (Some((_, tagged)), None) => Some(tagged.clone()),
+            _ => tagged_path(query),
+        }
+    }
+
+    // A parser for a doc page, reusing the parsed page if it hasn't changed on disk
+    fn parser(&mut self, tagged_path: TaggedPath) -> Option<DocParser> {
+        let path = PathBuf::from(tagged_path.path());
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+
+        let cached = match self.documents.get(&path) {
+            Some((stamp, doc)) if *stamp == modified => Rc::clone(doc),
+            _ => {
+                let doc = Rc::new(Document::from_read(fs::File::open(&path).ok()?).ok()?);
+                if self.documents.len() >= MAX_CACHED_DOCUMENTS {
+                    self.documents.clear();
+                }
+                self.documents.insert(path, (modified, Rc::clone(&doc)));
+                doc
+            }
+        };
+
+        Some(DocParser::from_document(tagged_path, cached))
+    }
+}
+
+/// Serve requests read from stdin, writing responses to stdout until stdin is closed
+pub fn serve_stdio() -> io::Result<()> {
+    colored::control::set_override(false);
+    let mut server = Server::new();
+    let stdin = io::stdin();
+
+    serve(&mut server, stdin.lock(), io::stdout())
+}
+
+/**
+ * Serve requests from connections to a unix socket at the given path, one connection at a
+ * time. The socket is removed again when a client asks the server to shut down.
+ */
+#[cfg(unix)]
+pub fn serve_socket(socket: &Path) -> io::Result<()> {
+    use std::os::unix::net::UnixListener;
+
+    colored::control::set_override(false);
+    if socket.exists() {
+        fs::remove_file(socket)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    let mut server = Server::new();
+
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let reader = io::BufReader::new(stream.try_clone()?);
+        if let Err(e) = serve(&mut server, reader, stream) {
+            eprintln!("{}", e);
+        }
+        if server.shutdown {
+            break;
+        }
+    }
+
+    fs::remove_file(socket)
+}
+
+/// Unix sockets are not available on this platform
+#[cfg(not(unix))]
+pub fn serve_socket(_socket: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "unix sockets are not supported on this platform",
+    ))
+}
+
+fn serve<R: BufRead, W: Write>(server: &mut Server, reader: R, mut writer: W) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle(&line) {
+            writeln!(writer, "{}", response)?;
+            writer.flush()?;
+        }
+        if server.shutdown {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+fn string_param<'a>(params: &'a Value, name: &str) -> Result<&'a str, (i64, String)> {
+    params.get(name).and_then(Value::as_str).ok_or_else(|| {
+        (
+            INVALID_PARAMS,
+            format!("missing string parameter: {}", name),
+        )
+    })
+}
+
+// The query for a request, either given directly or as a cursor position in a source file
+fn query_param(params: &Value) -> Result<String, (i64, String)> {
+    if let Some(query) = params.get("query").and_then(Value::as_str) {
+        return Ok(query.to_string());
+    }
+
+    let file = string_param(params, "file")?;
+    let position = |name| params.get(name).and_then(Value::as_u64).map(|n| n as usize);
+    match (position("line"), position("column")) {
+        (Some(line), Some(column)) => {
+            cursor::query_at(Path::new(file), line, column).ok_or_else(|| {
+                (
+                    INVALID_PARAMS,
+                    format!("no symbol at {}:{}:{}", file, line, column),
+                )
+            })
+        }
+        _ => Err((
+            INVALID_PARAMS,
+            String::from("missing query or cursor position"),
+        )),
+    }
+}
+
+fn tagged_path(query: &str) -> Option<TaggedPath> {
+    Locator::try_new(query.to_string())?
+        .determine_tagged_path()
+        .or_else(|| locate::keyword_tagged_path(query))
+}
+
+// The latest modification time of a doc root and its direct children. Running rustdoc
+// rewrites the index files at the top level so this changes whenever the docs are rebuilt.
+fn root_stamp(root: &Path) -> Option<SystemTime> {
+    let mut stamp = fs::metadata(root).and_then(|m| m.modified()).ok()?;
+    for entry in fs::read_dir(root).ok()?.filter_map(|e| e.ok()) {
+        if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+            stamp = stamp.max(modified);
+        }
+    }
+
+    Some(stamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn request(server: &mut Server, method: &str, params: Value) -> Value {
+        let req = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        server.handle(&req.to_string()).unwrap()
+    }
+
+    fn test_server() -> Server {
+        Server::with_roots(vec![PathBuf::from("test_resources/diff/after")])
+    }
+
+    #[test_case("foo::", json!(["foo::Kind", "foo::Thing", "foo::new"]); "children")]
+    #[test_case("foo::T", json!(["foo::Thing"]); "prefix")]
+    #[test_case("bar", json!([]); "no matches")]
+    fn complete_requests(partial: &str, expected: Value) {
+        let res = request(
+            &mut test_server(),
+            "complete",
+            json!({ "partial": partial }),
+        );
+
+        assert_eq!(res["result"], expected);
+    }
+
+    #[test_case("thing", &["foo::Thing"]; "exact name")]
+    #[test_case("n", &["foo::new", "foo::Kind", "foo::Thing"]; "ranked")]
+    #[test_case("foo", &["foo", "foo::new", "foo::Kind", "foo::Thing"]; "module path")]
+    fn search_requests(query: &str, expected: &[&str]) {
+        let res = request(&mut test_server(), "search", json!({ "query": query }));
+        let items: Vec<&str> = res["result"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["item"].as_str().unwrap())
+            .collect();
+
+        assert_eq!(items, expected);
+    }
+
+    #[test_case("{not json", PARSE_ERROR; "parse error")]
+    #[test_case(r#"{"jsonrpc":"2.0","id":1}"#, INVALID_REQUEST; "missing method")]
+    #[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"frobnicate"}"#, METHOD_NOT_FOUND; "unknown method")]
+    #[test_case(r#"{"jsonrpc":"2.0","id":1,"method":"search","params":{}}"#, INVALID_PARAMS; "missing params")]
+    fn invalid_requests(line: &str, code: i64) {
+        let res = test_server().handle(line).unwrap();
+
+        assert_eq!(res["error"]["code"], json!(code));
+    }
+
+    #[test]
+    fn indexed_items_are_resolved_without_the_locator() {
+        let res = request(
+            &mut test_server(),
+            "resolve",
+            json!({ "query": "foo::Thing" }),
+        );
+
+        assert_eq!(res["result"]["item"], json!("foo::Thing"));
+        assert_eq!(res["result"]["kind"], json!("struct"));
+    }
+
+    #[test]
+    fn invalid_grep_patterns_are_rejected() {
+        let params = json!({ "query": "foo::Thing", "grep": "(" });
+        let res = request(&mut test_server(), "render", params);
+
+        assert_eq!(res["error"]["code"], json!(INVALID_PARAMS));
+    }
+
+    #[test]
+    fn notifications_get_no_response() {
+        let mut server = test_server();
+
+        assert_eq!(
+            server.handle(r#"{"jsonrpc":"2.0","method":"shutdown"}"#),
+            None
+        );
+        assert!(server.shutdown);
+    }
+}
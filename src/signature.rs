@@ -0,0 +1,429 @@
+/*!
+ * Pretty printing of item signatures and declarations from syn token streams
+ *
+ * Tokens are first grouped into atoms (identifiers, multi-character operators, lifetimes
+ * and delimited groups) which are then joined with rustfmt-like spacing. Declarations that
+ * don't fit within the output width have their where-clause, parameter list and generic
+ * parameter list broken onto multiple lines in that order.
+ */
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use syn::WhereClause;
+
+// Indentation used for broken lists
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Atom {
+    Word(String),
+    Op(String),
+    Lifetime(String),
+    Group(Delimiter, Vec<Atom>),
+}
+
+impl Atom {
+    fn is_word(&self, w: &str) -> bool {
+        matches!(self, Atom::Word(s) if s == w)
+    }
+
+    fn is_op(&self, op: &str) -> bool {
+        matches!(self, Atom::Op(s) if s == op)
+    }
+}
+
+/// Render tokens on a single line with rustfmt-like spacing
+pub(crate) fn flat<T: ToTokens>(tokens: &T) -> String {
+    join(&atoms(tokens.to_token_stream()))
+}
+
+/**
+ * Render a function signature or other declaration, wrapping it to fit within `width`.
+ * `indent` is the number of columns already used on the first line (e.g. by a visibility
+ * prefix that is printed separately).
+ */
+pub(crate) fn wrapped<T: ToTokens>(tokens: &T, indent: usize, width: usize) -> String {
+    let atoms = atoms(tokens.to_token_stream());
+    let s = join(&atoms);
+    if indent + s.len() <= width {
+        return s;
+    }
+
+    let (head, preds) = match atoms.iter().position(|a| a.is_word("where")) {
+        Some(ix) => (&atoms[..ix], split_list(&atoms[ix + 1..])),
+        None => (&atoms[..], vec![]),
+    };
+
+    let head = wrap_head(head, indent, width);
+    with_where_clause(
+        head,
+        &preds.iter().map(|p| join(p)).collect::<Vec<_>>(),
+        width,
+    )
+}
+
+/// The predicates of a where clause, each rendered on a single line
+pub(crate) fn where_predicates(where_clause: &Option<WhereClause>) -> Vec<String> {
+    match where_clause {
+        Some(w) => w.predicates.iter().map(flat).collect(),
+        None => vec![],
+    }
+}
+
+/// Append a where clause to a declaration head, on the same line if it fits
+pub(crate) fn with_where_clause(head: String, preds: &[String], width: usize) -> String {
+    if preds.is_empty() {
+        return head;
+    }
+
+    let inline = format!("{} where {}", head, preds.join(", "));
+    if !head.contains('\n') && inline.len() <= width {
+        return inline;
+    }
+
+    let preds: String = preds
+        .iter()
+        .map(|p| format!("\n{}{},", INDENT, p))
+        .collect();
+    format!("{}\nwhere{}", head, preds)
+}
+
+// Break the parameter list of a declaration (and then its generics) if it is too long
+fn wrap_head(head: &[Atom], indent: usize, width: usize) -> String {
+    let s = join(head);
+    if indent + s.len() <= width {
+        return s;
+    }
+
+    let params = head
+        .iter()
+        .position(|a| matches!(a, Atom::Group(Delimiter::Parenthesis, _)));
+    let generics = generics_span(head);
+
+    match (params, generics) {
+        (Some(p), generics) => {
+            let inner = match &head[p] {
+                Atom::Group(_, inner) => inner,
+                _ => unreachable!(),
+            };
+            let before = match generics {
+                Some((start, end)) if end < p && indent + join(&head[..=p]).len() > width => {
+                    broken_generics(head, start, end, p)
+                }
+                _ => join(&head[..p]),
+            };
+            let rest = join(&head[p + 1..]);
+            let sep = if rest.is_empty() || rest.starts_with(';') {
+                ""
+            } else {
+                " "
+            };
+
+            format!("{}({}\n){}{}", before, broken_list(inner), sep, rest)
+        }
+        (None, Some((start, end))) => {
+            let before = broken_generics(head, start, end, end + 1);
+            let rest = join(&head[end + 1..]);
+            let sep = if rest.is_empty() { "" } else { " " };
+            format!("{}{}{}", before, sep, rest)
+        }
+        (None, None) => s,
+    }
+}
+
+// Render head[..until] with the generic list between `start` and `end` broken one per line
+fn broken_generics(head: &[Atom], start: usize, end: usize, until: usize) -> String {
+    format!(
+        "{}<{}\n>{}",
+        join(&head[..start]),
+        broken_list(&head[start + 1..end]),
+        join(&head[end + 1..until])
+    )
+}
+
+// Each comma separated element on its own indented line, with a trailing comma
+fn broken_list(atoms: &[Atom]) -> String {
+    split_list(atoms)
+        .iter()
+        .map(|item| format!("\n{}{},", INDENT, join(item)))
+        .collect()
+}
+
+// The positions of the `<` and `>` of the first top level generic parameter list
+fn generics_span(atoms: &[Atom]) -> Option<(usize, usize)> {
+    let start = atoms.iter().position(|a| a.is_op("<"))?;
+    let mut depth = 0;
+    for (i, a) in atoms.iter().enumerate().skip(start) {
+        match a {
+            Atom::Op(op) if op == "<" => depth += 1,
+            Atom::Op(op) if op == ">" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((start, i));
+                }
+            }
+            Atom::Op(op) if op == ">>" => {
+                depth -= 2;
+                if depth <= 0 {
+                    return Some((start, i));
+                }
+            }
+            _ => (),
+        }
+    }
+
+    None
+}
+
+// Split atoms on commas that are not nested within generic brackets
+fn split_list(atoms: &[Atom]) -> Vec<&[Atom]> {
+    let mut items = vec![];
+    let mut depth = 0i32;
+    let mut start = 0;
+
+    for (i, a) in atoms.iter().enumerate() {
+        match a {
+            Atom::Op(op) if op == "<" => depth += 1,
+            Atom::Op(op) if op == ">" => depth -= 1,
+            Atom::Op(op) if op == ">>" => depth -= 2,
+            Atom::Op(op) if op == "," && depth <= 0 => {
+                items.push(&atoms[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    if start < atoms.len() {
+        items.push(&atoms[start..]);
+    }
+
+    items
+}
+
+// Group raw tokens into words, combined operators, lifetimes and delimited groups
+fn atoms(tokens: TokenStream) -> Vec<Atom> {
+    let mut atoms = vec![];
+    let mut iter = tokens.into_iter().peekable();
+
+    while let Some(tt) = iter.next() {
+        match tt {
+            TokenTree::Ident(i) => atoms.push(Atom::Word(i.to_string())),
+            TokenTree::Literal(l) => atoms.push(Atom::Word(l.to_string())),
+            TokenTree::Group(g) => atoms.push(Atom::Group(g.delimiter(), self::atoms(g.stream()))),
+            TokenTree::Punct(p) if p.as_char() == '\'' => match iter.next() {
+                Some(TokenTree::Ident(i)) => atoms.push(Atom::Lifetime(format!("'{}", i))),
+                Some(other) => {
+                    atoms.push(Atom::Op("'".to_string()));
+                    atoms.extend(self::atoms(other.into_token_stream()));
+                }
+                None => atoms.push(Atom::Op("'".to_string())),
+            },
+            TokenTree::Punct(p) => {
+                let mut op = p.as_char().to_string();
+                let mut spacing = p.spacing();
+                while spacing == Spacing::Joint {
+                    match iter.peek() {
+                        Some(TokenTree::Punct(next))
+                            if is_operator(&format!("{}{}", op, next.as_char())) =>
+                        {
+                            op.push(next.as_char());
+                            spacing = next.spacing();
+                            iter.next();
+                        }
+                        _ => break,
+                    }
+                }
+                atoms.push(Atom::Op(op));
+            }
+        }
+    }
+
+    atoms
+}
+
+// Join atoms with rustfmt-like spacing
+fn join(atoms: &[Atom]) -> String {
+    let mut s = String::new();
+
+    for (i, atom) in atoms.iter().enumerate() {
+        if i > 0 && needs_space(&atoms[..i], atom) {
+            s.push(' ');
+        }
+
+        match atom {
+            Atom::Word(w) | Atom::Op(w) | Atom::Lifetime(w) => s.push_str(w),
+            Atom::Group(delim, inner) => {
+                let inner = join(inner);
+                match delim {
+                    Delimiter::Parenthesis => s.push_str(&format!("({})", inner)),
+                    Delimiter::Bracket => s.push_str(&format!("[{}]", inner)),
+                    Delimiter::Brace if inner.is_empty() => s.push_str("{}"),
+                    Delimiter::Brace => s.push_str(&format!("{{ {} }}", inner)),
+                    Delimiter::None => s.push_str(&inner),
+                }
+            }
+        }
+    }
+
+    s
+}
+
+// Whether a space is needed between the atoms so far and the next one
+fn needs_space(before: &[Atom], next: &Atom) -> bool {
+    let prev = &before[before.len() - 1];
+    let prev_prev = before.len().checked_sub(2).map(|i| &before[i]);
+
+    // Operators that bind to what follows them
+    if let Atom::Op(op) = prev {
+        match op.as_str() {
+            "::" | "#" | "$" | "?" | "'" | "<" | "!" | "." | ".." | "..=" => return false,
+            // References, raw pointers and negative numbers
+            "&" | "&&" | "*" | "-" if is_prefix_position(prev_prev) => return false,
+            _ => (),
+        }
+    }
+
+    match next {
+        Atom::Op(op) => match op.as_str() {
+            "," | ";" | ":" | "::" | "." | ">" | ">>" => false,
+            "?" => {
+                !matches!(prev, Atom::Word(w) if !is_keyword(w)) && !matches!(prev, Atom::Group(..))
+            }
+            "<" => matches!(prev, Atom::Op(op) if op != ">" && op != ">>"),
+            "!" => !matches!(prev, Atom::Word(w) if !is_keyword(w)),
+            _ => true,
+        },
+        Atom::Group(Delimiter::Parenthesis, _) => match prev {
+            Atom::Word(w) => {
+                is_keyword(w) && !matches!(w.as_str(), "fn" | "pub" | "self" | "Self" | "crate")
+            }
+            Atom::Op(op) => !matches!(op.as_str(), ">" | ">>" | "!"),
+            _ => true,
+        },
+        Atom::Group(Delimiter::Bracket, _) => match prev {
+            Atom::Word(w) => is_keyword(w),
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+// Whether an operator following `atom` is in prefix (unary) position
+fn is_prefix_position(atom: Option<&Atom>) -> bool {
+    match atom {
+        None => true,
+        Some(Atom::Op(op)) => !matches!(op.as_str(), ">" | ">>" | "?"),
+        Some(Atom::Word(w)) => is_keyword(w),
+        _ => false,
+    }
+}
+
+// Multi-character operators that are kept together when joining punctuation
+fn is_operator(op: &str) -> bool {
+    matches!(
+        op,
+        "::" | "->"
+            | "=>"
+            | "=="
+            | "!="
+            | "<="
+            | ">="
+            | "&&"
+            | "||"
+            | ".."
+            | "..="
+            | "..."
+            | "<<"
+            | ">>"
+            | "+="
+            | "-="
+            | "*="
+            | "/="
+            | "%="
+            | "^="
+            | "&="
+            | "|="
+            | "<<="
+            | ">>="
+    )
+}
+
+fn is_keyword(w: &str) -> bool {
+    matches!(
+        w,
+        "as" | "const"
+            | "crate"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "let"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "pub"
+            | "ref"
+            | "return"
+            | "self"
+            | "Self"
+            | "static"
+            | "struct"
+            | "trait"
+            | "type"
+            | "union"
+            | "unsafe"
+            | "use"
+            | "where"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::{parse_str, Signature, Type};
+    use test_case::test_case;
+
+    #[test_case("& 'a str", "&'a str"; "reference with lifetime")]
+    #[test_case("& mut Vec < u8 >", "&mut Vec<u8>"; "mutable reference")]
+    #[test_case("std :: io :: Result < () >", "std::io::Result<()>"; "paths")]
+    #[test_case("Box < dyn Fn (& str) -> bool + Send >", "Box<dyn Fn(&str) -> bool + Send>"; "closure bounds")]
+    #[test_case("HashMap < String , Vec < Option < T > > >", "HashMap<String, Vec<Option<T>>>"; "nested generics")]
+    #[test_case("[u8 ; 4]", "[u8; 4]"; "array")]
+    #[test_case("* const T", "*const T"; "raw pointer")]
+    #[test_case("(A , B)", "(A, B)"; "tuple")]
+    #[test_case("impl Iterator < Item = & 'a T >", "impl Iterator<Item = &'a T>"; "impl trait")]
+    fn types_are_formatted(input: &str, expected: &str) {
+        let ty: Type = parse_str(input).unwrap();
+        assert_eq!(flat(&ty), expected);
+    }
+
+    #[test_case(
+        "fn get<'a, T: ?Sized>(&'a self, key: &T) -> Option<&'a V>",
+        "fn get<'a, T: ?Sized>(&'a self, key: &T) -> Option<&'a V>";
+        "fits on one line"
+    )]
+    #[test_case(
+        "fn insert(&mut self, key: String, value: Vec<u8>, overwrite: bool) -> Result<(), Error>",
+        "fn insert(\n    &mut self,\n    key: String,\n    value: Vec<u8>,\n    overwrite: bool,\n) -> Result<(), Error>";
+        "long parameter list"
+    )]
+    #[test_case(
+        "fn map<F, T>(self, f: F) -> Mapped<T> where F: FnMut(Self::Item) -> T, T: Clone",
+        "fn map<F, T>(self, f: F) -> Mapped<T>\nwhere\n    F: FnMut(Self::Item) -> T,\n    T: Clone,";
+        "long where clause"
+    )]
+    #[test_case(
+        "fn zip<AVeryLongTypeName: Clone, AnotherLongName: Copy>(a: AVeryLongTypeName)",
+        "fn zip<\n    AVeryLongTypeName: Clone,\n    AnotherLongName: Copy,\n>(\n    a: AVeryLongTypeName,\n)";
+        "long generic list"
+    )]
+    fn signatures_are_wrapped(input: &str, expected: &str) {
+        let sig: Signature = parse_str(input).unwrap();
+        assert_eq!(wrapped(&sig, 0, 60), expected);
+    }
+}
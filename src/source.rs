@@ -0,0 +1,142 @@
+/*!
+ * Answer queries from crate source code when there is no rustdoc output available
+ */
+use crate::{
+    extract::{extract_crate, CrateItems, Location},
+    highlight::highlight,
+    locate::{get_crate_source_root, TaggedPath},
+    parse::{matching_lines, DocParser},
+    pprint::{header, SOURCE_HEADING_COLOR},
+};
+use std::{fs, ops::RangeInclusive, path::Path};
+
+/**
+ * Resolve a query path against the source of its crate, printing the matching
+ * items if we are able to find them. Returns false if the query can't be resolved.
+ */
+pub fn show_from_source(query: &str, grep: Option<String>) -> bool {
+    match render_from_source(query, grep) {
+        Some(s) => {
+            println!("{}", s);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Resolve a query path against the source of its crate, formatting the matching items
+pub fn render_from_source(query: &str, grep: Option<String>) -> Option<String> {
+    let (krate, components) = extract_query_crate(query)?;
+    let (module, remaining) = krate.resolve(&components)?;
+    let items = krate.module(&module)?;
+
+    let rendered = match remaining.as_slice() {
+        [] => items.render_all(),
+        [name] => items.render_item(name)?,
+        [ty, method] => items.render_method(ty, method)?,
+        _ => return None,
+    };
+
+    let s = match grep {
+        Some(grep_str) => match matching_lines(rendered, &grep_str) {
+            Ok(lines) => lines,
+            Err(e) => panic!("{}", e),
+        },
+        None => rendered,
+    };
+
+    let file = krate.file(&module).unwrap_or_else(|| Path::new(query));
+    let title = header(
+        &format!("{} (from source)", file.display()),
+        SOURCE_HEADING_COLOR,
+    );
+    Some(format!("{}\n{}", title, s))
+}
+
+/// Find where the item named by a query path is defined in the source of its crate
+pub fn source_location(query: &str) -> Option<Location> {
+    let (krate, components) = extract_query_crate(query)?;
+    krate.location(&components)
+}
+
+/**
+ * Print the highlighted source code of the item named by a query. The code is found via
+ * the rustdoc page of the item when there is one, otherwise from the source of its crate.
+ * Returns false if the source can't be found.
+ */
+pub fn show_item_source(query: &str, tagged_path: Option<TaggedPath>) -> bool {
+    let found = tagged_path
+        .and_then(|p| DocParser::try_new(p).ok()?.item_source())
+        .or_else(|| {
+            let loc = source_location(query)?;
+            let code = select_lines(&fs::read_to_string(&loc.file).ok()?, loc.lines)?;
+            Some((loc.file, code))
+        });
+
+    match found {
+        Some((file, code)) => {
+            let title = header(&file.display().to_string(), SOURCE_HEADING_COLOR);
+            println!("{}\n{}", title, highlight(&code));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Select a range of lines (starting from 1) from some code, removing their common indent
+pub(crate) fn select_lines(code: &str, lines: RangeInclusive<usize>) -> Option<String> {
+    let selected: Vec<&str> = code
+        .lines()
+        .skip(lines.start().checked_sub(1)?)
+        .take(lines.end().checked_sub(*lines.start())? + 1)
+        .collect();
+    if selected.is_empty() {
+        return None;
+    }
+
+    let indent = selected
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    Some(
+        selected
+            .iter()
+            .map(|l| l.get(indent..).unwrap_or("").trim_end())
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+// Extract the crate named by the first component of a query path, along with the
+// components of the query itself
+fn extract_query_crate(query: &str) -> Option<(CrateItems, Vec<String>)> {
+    let components: Vec<String> = query
+        .split("::")
+        .flat_map(|s| s.split('.'))
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+
+    let entry = get_crate_source_root(components.first()?)?;
+    let krate = extract_crate(&components[0], &entry).ok()?;
+
+    Some((krate, components))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case(2..=4, "fn foo() {\n    1\n}"; "removes common indent")]
+    #[test_case(1..=1, "impl Foo {"; "single line")]
+    #[test_case(4..=9, "    }\n}"; "clamped to the end")]
+    fn lines_are_selected(lines: RangeInclusive<usize>, expected: &str) {
+        let code = "impl Foo {\n    fn foo() {\n        1\n    }\n}\n";
+
+        assert_eq!(select_lines(code, lines), Some(expected.to_string()));
+    }
+}
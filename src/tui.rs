@@ -0,0 +1,801 @@
+/*!
+ * An interactive terminal browser for local rustdoc output
+ *
+ * The screen is split into a tree of the modules in the current crate, a list of the items in
+ * the current module and the rendered docs of the current page. Links on the page can be
+ * followed, with back and forward history, and there is an incremental fuzzy search over every
+ * item under the current doc root. Everything is read from local files so it works offline.
+ */
+use crate::{
+    locate::{self, Tag, TaggedPath},
+    parse::DocParser,
+};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Print, SetAttribute},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::{
+    ffi::OsStr,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+const HELP: &str =
+    "tab: pane  enter: open  /: search  f: follow link  h/l: back/forward  o: browser  q: quit";
+
+// The number of lines moved by page up and page down
+const PAGE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Pane {
+    Modules,
+    Items,
+    Docs,
+}
+
+// An entry in one of the lists shown by the browser along with the page it opens
+#[derive(Debug, Clone)]
+struct Entry {
+    label: String,
+    target: TaggedPath,
+}
+
+#[derive(Debug, Default)]
+struct List {
+    entries: Vec<Entry>,
+    selected: usize,
+}
+
+impl List {
+    fn new(entries: Vec<Entry>) -> Self {
+        List {
+            entries,
+            selected: 0,
+        }
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        self.entries.get(self.selected)
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.entries.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+    }
+
+    fn select_target(&mut self, target: &TaggedPath) {
+        if let Some(i) = self
+            .entries
+            .iter()
+            .position(|e| e.target.path() == target.path())
+        {
+            self.selected = i;
+        }
+    }
+
+    fn labels(&self) -> Vec<String> {
+        self.entries.iter().map(|e| e.label.clone()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PickerKind {
+    Search,
+    Links,
+}
+
+// A popup list of candidates filtered by fuzzy matching against the text typed so far
+struct Picker {
+    kind: PickerKind,
+    input: String,
+    candidates: Vec<Entry>,
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+impl Picker {
+    fn new(kind: PickerKind, candidates: Vec<Entry>) -> Self {
+        let mut picker = Picker {
+            kind,
+            input: String::new(),
+            candidates,
+            matches: vec![],
+            selected: 0,
+        };
+        picker.update();
+        picker
+    }
+
+    fn update(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .candidates
+            .iter()
+            .enumerate()
+            .filter_map(|(i, e)| fuzzy_score(&self.input, &e.label).map(|s| (s, i)))
+            .collect();
+        scored.sort_by_key(|&(score, i)| (-score, self.candidates[i].label.len(), i));
+
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        self.matches
+            .get(self.selected)
+            .map(|&i| &self.candidates[i])
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + delta).clamp(0, last) as usize;
+    }
+}
+
+struct Browser {
+    current: TaggedPath,
+    back: Vec<TaggedPath>,
+    forward: Vec<TaggedPath>,
+    modules: List,
+    items: List,
+    doc: Vec<String>,
+    scroll: usize,
+    links: Vec<Entry>,
+    focus: Pane,
+    picker: Option<Picker>,
+    tree_of: Option<String>,
+    index: Option<(Option<PathBuf>, Vec<Entry>)>,
+    status: String,
+}
+
+impl Browser {
+    fn new(start: TaggedPath) -> Self {
+        let mut browser = Browser {
+            current: start.clone(),
+            back: vec![],
+            forward: vec![],
+            modules: List::default(),
+            items: List::default(),
+            doc: vec![],
+            scroll: 0,
+            links: vec![],
+            focus: Pane::Items,
+            picker: None,
+            tree_of: None,
+            index: None,
+            status: String::new(),
+        };
+        browser.load(start);
+        browser
+    }
+
+    fn navigate(&mut self, target: TaggedPath) {
+        let previous = self.current.clone();
+        self.back.push(previous);
+        self.forward.clear();
+        self.load(target);
+    }
+
+    fn go_back(&mut self) {
+        match self.back.pop() {
+            Some(target) => {
+                self.forward.push(self.current.clone());
+                self.load(target);
+            }
+            None => self.status = String::from("no previous page"),
+        }
+    }
+
+    fn go_forward(&mut self) {
+        match self.forward.pop() {
+            Some(target) => {
+                self.back.push(self.current.clone());
+                self.load(target);
+            }
+            None => self.status = String::from("no next page"),
+        }
+    }
+
+    // Show the given page, updating the module tree and item list to match
+    fn load(&mut self, target: TaggedPath) {
+        let parser = DocParser::try_new(target.clone()).ok();
+        let rendered = parser.as_ref().and_then(|p| p.try_render(None, false).ok());
+        self.doc = match rendered {
+            Some(s) => s.lines().map(String::from).collect(),
+            None => vec![format!("unable to render {}", target.path())],
+        };
+        self.links = parser
+            .map(|p| p.links())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|href| locate::resolve_link(&target, href))
+            .map(|t| Entry {
+                label: item_label(&t),
+                target: t,
+            })
+            .collect();
+        self.scroll = 0;
+        self.status.clear();
+
+        if let Some(root) = target.root() {
+            let module_path = target.module_path();
+            let krate = module_path.first().cloned().unwrap_or_default();
+            if self.tree_of.as_ref() != Some(&krate) {
+                self.modules = List::new(module_tree(&root, &krate));
+                self.tree_of = Some(krate);
+            }
+
+            let dir = module_path.iter().fold(root.clone(), |p, c| p.join(c));
+            let module = TaggedPath::with_root(dir.join("index.html"), &root);
+            self.modules.select_target(&module);
+            self.items = List::new(module_items(&root, &dir));
+            self.items.select_target(&target);
+        }
+
+        self.current = target;
+    }
+
+    // Handle a key press, returning false when the browser should exit
+    fn handle_key(&mut self, key: KeyEvent, open: &dyn Fn(TaggedPath)) -> bool {
+        if self.picker.is_some() {
+            self.handle_picker_key(key);
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Pane::Modules => Pane::Items,
+                    Pane::Items => Pane::Docs,
+                    Pane::Docs => Pane::Modules,
+                }
+            }
+            KeyCode::BackTab => {
+                self.focus = match self.focus {
+                    Pane::Modules => Pane::Docs,
+                    Pane::Items => Pane::Modules,
+                    Pane::Docs => Pane::Items,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(PAGE_SIZE as isize)),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.move_by(PAGE_SIZE as isize),
+            KeyCode::Enter => {
+                let selected = match self.focus {
+                    Pane::Modules => self.modules.selected(),
+                    Pane::Items => self.items.selected(),
+                    Pane::Docs => None,
+                };
+                if let Some(entry) = selected.cloned() {
+                    if entry.target != self.current {
+                        self.navigate(entry.target);
+                    }
+                }
+            }
+            KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => self.go_back(),
+            KeyCode::Right | KeyCode::Char('l') => self.go_forward(),
+            KeyCode::Char('/') => {
+                let index = self.search_index();
+                self.picker = Some(Picker::new(PickerKind::Search, index));
+            }
+            KeyCode::Char('f') if self.links.is_empty() => {
+                self.status = String::from("no links on this page")
+            }
+            KeyCode::Char('f') => {
+                self.picker = Some(Picker::new(PickerKind::Links, self.links.clone()));
+            }
+            KeyCode::Char('o') => {
+                open(self.current.clone());
+                self.status = format!("opened {} in the browser", item_label(&self.current));
+            }
+            _ => (),
+        }
+
+        true
+    }
+
+    fn handle_picker_key(&mut self, key: KeyEvent) {
+        let picker = match self.picker.as_mut() {
+            Some(p) => p,
+            None => return,
+        };
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => self.picker = None,
+            KeyCode::Enter => {
+                let target = picker.selected().map(|e| e.target.clone());
+                self.picker = None;
+                if let Some(target) = target {
+                    self.navigate(target);
+                }
+            }
+            KeyCode::Up => picker.move_by(-1),
+            KeyCode::Char('p') if ctrl => picker.move_by(-1),
+            KeyCode::Down => picker.move_by(1),
+            KeyCode::Char('n') if ctrl => picker.move_by(1),
+            KeyCode::Backspace => {
+                picker.input.pop();
+                picker.update();
+            }
+            KeyCode::Char(c) => {
+                picker.input.push(c);
+                picker.update();
+            }
+            _ => (),
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        match self.focus {
+            Pane::Modules => self.modules.move_by(delta),
+            Pane::Items => self.items.move_by(delta),
+            Pane::Docs => {
+                let last = self.doc.len().saturating_sub(1) as isize;
+                self.scroll = (self.scroll as isize + delta).clamp(0, last) as usize;
+            }
+        }
+    }
+
+    // Every item under the doc root of the current page, indexed the first time we search
+    // from a page under that root
+    fn search_index(&mut self) -> Vec<Entry> {
+        let root = self.current.root();
+        if self.index.as_ref().map(|(r, _)| r) != Some(&root) {
+            let tagged_paths = match &root {
+                Some(root) => locate::walk_tagged_paths(root, &[]).unwrap_or_default(),
+                None => vec![],
+            };
+            let entries = tagged_paths
+                .into_iter()
+                .map(|t| Entry {
+                    label: item_label(&t),
+                    target: t,
+                })
+                .collect();
+            self.index = Some((root, entries));
+        }
+
+        self.index
+            .as_ref()
+            .map(|(_, entries)| entries.clone())
+            .unwrap_or_default()
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let (cols, rows) = terminal::size()?;
+        let (cols, rows) = (cols as usize, rows as usize);
+        let body = rows.saturating_sub(1);
+        let left = (cols / 3).clamp(20.min(cols), cols);
+        let tree_height = body / 2;
+
+        // The panes overwrite every cell they cover so the screen is only cleared on resize
+        let modules = Rect::new(0, 0, left, tree_height);
+        let items = Rect::new(0, tree_height, left, body - tree_height);
+        let docs = Rect::new(left, 0, cols - left, body);
+
+        let selected = Some(self.modules.selected);
+        let focused = self.focus == Pane::Modules;
+        draw_pane(
+            out,
+            modules,
+            "Modules",
+            focused,
+            &self.modules.labels(),
+            selected,
+        )?;
+        let selected = Some(self.items.selected);
+        let focused = self.focus == Pane::Items;
+        draw_pane(out, items, "Items", focused, &self.items.labels(), selected)?;
+
+        let wrapped: Vec<String> = self.doc[self.scroll.min(self.doc.len())..]
+            .iter()
+            .flat_map(|l| wrap(l, docs.width.saturating_sub(2)))
+            .collect();
+        let title = item_label(&self.current);
+        draw_pane(out, docs, &title, self.focus == Pane::Docs, &wrapped, None)?;
+
+        let status = if self.status.is_empty() {
+            HELP
+        } else {
+            &self.status
+        };
+        queue!(
+            out,
+            MoveTo(0, body as u16),
+            Print(truncate(status, cols)),
+            Clear(ClearType::UntilNewLine)
+        )?;
+
+        if let Some(picker) = &self.picker {
+            let (width, height) = ((cols * 3 / 4).max(20), (rows * 2 / 3).max(5));
+            let (x, y) = ((cols - width.min(cols)) / 2, (rows - height.min(rows)) / 2);
+            let rect = Rect::new(x, y, width, height);
+            let title = match picker.kind {
+                PickerKind::Search => "Search",
+                PickerKind::Links => "Follow link",
+            };
+            let mut lines = vec![format!("> {}", picker.input)];
+            lines.extend(
+                picker
+                    .matches
+                    .iter()
+                    .map(|&i| picker.candidates[i].label.clone()),
+            );
+            // Keep the selected match in view, leaving room for the input line
+            let visible = height.saturating_sub(3);
+            let skip = picker.selected.saturating_sub(visible.saturating_sub(1));
+            let shown: Vec<String> = lines[..1]
+                .iter()
+                .chain(&lines[1 + skip..])
+                .cloned()
+                .collect();
+            let selected = Some(picker.selected - skip + 1).filter(|_| !picker.matches.is_empty());
+            draw_pane(out, rect, title, true, &shown, selected)?;
+        }
+
+        Ok(())
+    }
+}
+
+// A region of the screen
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Rect {
+    fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Run the browser starting from the given page until the user quits
+pub fn run(start: TaggedPath, open: impl Fn(TaggedPath)) -> io::Result<()> {
+    colored::control::set_override(false);
+    let mut browser = Browser::new(start);
+    let _terminal = Terminal::enter()?;
+    let mut out = io::stdout();
+
+    loop {
+        browser.draw(&mut out)?;
+        out.flush()?;
+
+        let event = event::read()?;
+        if let Event::Resize(..) = event {
+            queue!(out, Clear(ClearType::All))?;
+        }
+        if let Event::Key(key) = event {
+            if key.kind != KeyEventKind::Release && !browser.handle_key(key, &open) {
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Puts the terminal into raw mode on the alternate screen, restoring it again when dropped
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Terminal)
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw_pane(
+    out: &mut impl Write,
+    rect: Rect,
+    title: &str,
+    focused: bool,
+    lines: &[String],
+    selected: Option<usize>,
+) -> io::Result<()> {
+    if rect.width < 2 || rect.height < 2 {
+        return Ok(());
+    }
+    let inner_width = rect.width - 2;
+    let inner_height = rect.height - 2;
+
+    // Scroll lists so that the selected line is always visible
+    let offset = selected.map_or(0, |s| s.saturating_sub(inner_height.saturating_sub(1)));
+
+    let heading = truncate(&format!(" {} ", title), inner_width);
+    let top = format!(
+        "┌{}{}┐",
+        heading,
+        "─".repeat(inner_width - heading.chars().count())
+    );
+    let bottom = format!("└{}┘", "─".repeat(inner_width));
+    if focused {
+        queue!(out, SetAttribute(Attribute::Bold))?;
+    }
+    queue!(out, MoveTo(rect.x as u16, rect.y as u16), Print(top))?;
+    queue!(
+        out,
+        MoveTo(rect.x as u16, (rect.y + rect.height - 1) as u16),
+        Print(bottom)
+    )?;
+    queue!(out, SetAttribute(Attribute::Reset))?;
+
+    for row in 0..inner_height {
+        let i = offset + row;
+        let text = lines
+            .get(i)
+            .map(|l| truncate(l, inner_width))
+            .unwrap_or_default();
+        let padding = " ".repeat(inner_width - text.chars().count());
+        let y = (rect.y + 1 + row) as u16;
+
+        queue!(out, MoveTo(rect.x as u16, y), Print("│"))?;
+        if selected == Some(i) {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        queue!(
+            out,
+            Print(text),
+            Print(padding),
+            SetAttribute(Attribute::Reset),
+            Print("│")
+        )?;
+    }
+
+    Ok(())
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    s.chars().take(width).collect()
+}
+
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() || width == 0 {
+        return vec![String::new()];
+    }
+
+    chars.chunks(width).map(|c| c.iter().collect()).collect()
+}
+
+// The query path of the item that a tagged path points at, e.g. std::vec::Vec.push
+fn item_label(tagged: &TaggedPath) -> String {
+    match &tagged.method_name {
+        Some(m) => format!("{}.{}", tagged.item_path(), m),
+        None => tagged.item_path(),
+    }
+}
+
+// Every module in a crate as an indented tree, starting from the crate root
+fn module_tree(root: &Path, krate: &str) -> Vec<Entry> {
+    let mut entries = vec![];
+    add_modules(root, &root.join(krate), 0, &mut entries);
+    entries
+}
+
+fn add_modules(root: &Path, dir: &Path, depth: usize, entries: &mut Vec<Entry>) {
+    let index = dir.join("index.html");
+    if !index.is_file() {
+        return;
+    }
+
+    let target = TaggedPath::with_root(index, root);
+    let name = dir.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    entries.push(Entry {
+        label: format!("{}{}", "  ".repeat(depth), name),
+        target,
+    });
+
+    let mut children: Vec<_> = match dir.read_dir() {
+        Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+        Err(_) => return,
+    };
+    children.sort();
+    for child in children.iter().filter(|c| c.is_dir()) {
+        add_modules(root, child, depth + 1, entries);
+    }
+}
+
+// The child modules followed by the items documented in a module directory
+fn module_items(root: &Path, dir: &Path) -> Vec<Entry> {
+    let paths: Vec<_> = match dir.read_dir() {
+        Ok(entries) => entries.filter_map(|e| e.ok().map(|e| e.path())).collect(),
+        Err(_) => return vec![],
+    };
+
+    let mut items: Vec<(bool, Entry)> = paths
+        .into_iter()
+        .filter_map(|path| {
+            let is_module = path.is_dir();
+            let page = if is_module {
+                path.join("index.html")
+            } else {
+                path
+            };
+            if page.extension() != Some(OsStr::new("html")) || !page.is_file() {
+                return None;
+            }
+            let target = TaggedPath::with_root(page, root);
+            if matches!(target.tag, Tag::Unknown | Tag::Keyword) {
+                return None;
+            }
+            if !is_module && target.tag == Tag::Module {
+                return None;
+            }
+
+            let label = if is_module {
+                format!("mod {}", target.module_path().last()?)
+            } else {
+                format!("{} {}", target.tag, target.item_name()?)
+            };
+            Some((is_module, Entry { label, target }))
+        })
+        .collect();
+
+    items.sort_by(|(a_mod, a), (b_mod, b)| b_mod.cmp(a_mod).then(a.label.cmp(&b.label)));
+    items.into_iter().map(|(_, e)| e).collect()
+}
+
+/**
+ * Score how well a query matches a candidate, returning None if the characters of the query
+ * don't all appear in order. Consecutive matches and matches at the start of a path
+ * component score more highly so "hmap" prefers HashMap over a longer scattered match.
+ */
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let mut score = 0;
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut previous: Option<char> = None;
+    let mut last_matched = false;
+
+    for c in candidate.chars() {
+        let q = match query_chars.peek() {
+            Some(&q) => q,
+            None => break,
+        };
+        if c.to_lowercase().eq(Some(q)) {
+            score += 1;
+            if last_matched {
+                score += 5;
+            }
+            if matches!(previous, None | Some(':' | '.')) {
+                score += 10;
+            }
+            query_chars.next();
+            last_matched = true;
+        } else {
+            last_matched = false;
+        }
+        previous = Some(c);
+    }
+
+    match query_chars.peek() {
+        Some(_) => None,
+        None => Some(score),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use test_case::test_case;
+
+    fn tagged(path: &str) -> TaggedPath {
+        TaggedPath::with_root(PathBuf::from(path), Path::new("test_resources/browse"))
+    }
+
+    #[test_case("", "anything", Some(0); "empty query")]
+    #[test_case("hm", "std::collections::HashMap", Some(12); "segment starts")]
+    #[test_case("vec", "std::vec::Vec", Some(23); "consecutive")]
+    #[test_case("xyz", "std::vec::Vec", None; "no match")]
+    #[test_case("ba", "abc", None; "out of order")]
+    fn queries_are_scored(query: &str, candidate: &str, expected: Option<i64>) {
+        assert_eq!(fuzzy_score(query, candidate), expected);
+    }
+
+    #[test]
+    fn closer_matches_are_ranked_first() {
+        let candidates = [
+            "std::collections::hash_map::HashMap",
+            "std::collections::HashMap",
+        ];
+        let entries = candidates
+            .iter()
+            .map(|c| Entry {
+                label: c.to_string(),
+                target: tagged("test_resources/browse/krate/index.html"),
+            })
+            .collect();
+        let mut picker = Picker::new(PickerKind::Search, entries);
+        picker.input = String::from("hashmap");
+        picker.update();
+
+        assert_eq!(
+            picker.selected().unwrap().label,
+            "std::collections::HashMap"
+        );
+    }
+
+    #[test]
+    fn modules_are_shown_as_a_tree() {
+        let tree = module_tree(Path::new("test_resources/browse"), "krate");
+        let labels: Vec<&str> = tree.iter().map(|e| e.label.as_str()).collect();
+
+        assert_eq!(labels, vec!["krate", "  shapes"]);
+    }
+
+    #[test]
+    fn search_index_follows_the_current_doc_root() {
+        let mut browser = Browser::new(tagged("test_resources/browse/krate/index.html"));
+        let labels = |b: &mut Browser| -> Vec<String> {
+            b.search_index().into_iter().map(|e| e.label).collect()
+        };
+        assert!(labels(&mut browser).contains(&String::from("krate::Point")));
+
+        let other = Path::new("test_resources/diff/after");
+        browser.navigate(TaggedPath::with_root(other.join("foo/index.html"), other));
+        let found = labels(&mut browser);
+
+        assert!(found.contains(&String::from("foo::Thing")));
+        assert!(!found.contains(&String::from("krate::Point")));
+    }
+
+    #[test]
+    fn module_items_are_listed() {
+        let root = Path::new("test_resources/browse");
+        let items = module_items(root, &root.join("krate"));
+        let labels: Vec<&str> = items.iter().map(|e| e.label.as_str()).collect();
+
+        assert_eq!(labels, vec!["mod shapes", "fn origin", "struct Point"]);
+    }
+
+    #[test]
+    fn links_are_followed_with_history() {
+        let mut browser = Browser::new(tagged("test_resources/browse/krate/index.html"));
+        let labels: Vec<&str> = browser.links.iter().map(|e| e.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["krate::shapes", "krate::Point", "krate::origin"]
+        );
+
+        let point = browser.links[1].target.clone();
+        browser.navigate(point);
+        assert_eq!(item_label(&browser.current), "krate::Point");
+        assert_eq!(browser.items.selected().unwrap().label, "struct Point");
+        assert_eq!(
+            browser
+                .links
+                .iter()
+                .map(|e| e.label.as_str())
+                .collect::<Vec<_>>(),
+            vec!["krate::origin", "krate::Point.norm"]
+        );
+
+        browser.go_back();
+        assert_eq!(item_label(&browser.current), "krate");
+        browser.go_forward();
+        assert_eq!(item_label(&browser.current), "krate::Point");
+        assert!(browser.forward.is_empty());
+    }
+}
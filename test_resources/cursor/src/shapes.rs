@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use crate::shapes::inner::Side as Edge;
+
+pub struct Square {
+    pub sides: HashMap<u8, Edge>,
+}
+
+impl Square {
+    pub fn new() -> Self {
+        Self { sides: HashMap::new() }
+    }
+}
+
+impl Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", Some(Square::new().sides.len()))
+    }
+}
+
+pub mod inner {
+    pub struct Side;
+
+    pub fn side() -> super::Square {
+        super::Square::new()
+    }
+}
@@ -0,0 +1,32 @@
+//! A crate with a public API that differs from its module layout
+mod private {
+    pub struct Reexported;
+    pub struct NotReexported;
+    pub(crate) fn crate_only() {}
+}
+
+mod globbed {
+    pub fn from_glob() {}
+    fn not_pub() {}
+}
+
+pub mod public {
+    pub use super::private::Reexported as Renamed;
+
+    #[doc(hidden)]
+    pub fn hidden() {}
+
+    pub fn visible() {}
+
+    mod inner {
+        #[macro_export]
+        macro_rules! exported {
+            () => {};
+        }
+    }
+}
+
+pub use globbed::*;
+pub(crate) use private::crate_only;
+pub use private::Reexported;
+pub use std::collections::HashMap;
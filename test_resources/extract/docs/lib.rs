@@ -0,0 +1,51 @@
+#![doc = include_str!("README.md")]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+/// Visible docs
+#[cfg_attr(docsrs, doc = "Only on docs.rs")]
+#[doc(alias = "len", alias = "size")]
+#[derive(Debug)]
+#[serde(rename_all = "camelCase", with = some::path!())]
+pub struct Length(usize);
+
+#[doc(alias("area", "surface"))]
+pub fn measure() {}
+
+#[doc(hidden)]
+pub fn internal() {}
+
+#[cfg_attr(docsrs, doc(hidden))]
+pub struct OnlyHiddenOnDocsRs;
+
+pub(crate) struct CrateOnly;
+
+impl CrateOnly {
+    pub fn build() -> Self {
+        CrateOnly
+    }
+}
+
+impl Length {
+    pub fn get(&self) -> usize {
+        self.0
+    }
+
+    fn private_helper(&self) {}
+
+    #[doc(hidden)]
+    pub const INTERNAL_LIMIT: usize = 8;
+}
+
+pub trait Measure {
+    type Unit;
+    #[doc(hidden)]
+    type Internal;
+}
+
+impl Measure for Length {
+    type Unit = usize;
+    #[doc(hidden)]
+    type Internal = ();
+}
+
+fn private_fn() {}
@@ -0,0 +1,2 @@
+//! A flat module file
+pub mod b;
@@ -0,0 +1,5 @@
+/// A point in space
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
@@ -0,0 +1 @@
+pub type Nested = u8;
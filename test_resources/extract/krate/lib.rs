@@ -0,0 +1,18 @@
+//! The crate root
+pub mod a;
+pub mod d;
+#[path = "renamed.rs"]
+pub mod c;
+
+pub mod e {
+    //! An inline module
+    pub mod f;
+
+    pub struct Inline;
+}
+
+impl a::b::Point {
+    pub fn origin() -> Self {
+        a::b::Point { x: 0, y: 0 }
+    }
+}
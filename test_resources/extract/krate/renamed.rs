@@ -0,0 +1 @@
+pub const FROM_PATH: u8 = 1;